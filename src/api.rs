@@ -0,0 +1,163 @@
+use crate::{process_list_with_options, Cfg, Helper, ListOptions, Log, Stats, Warning};
+use anyhow::{Context as _, Result};
+use std::mem::take;
+use tes3::esp::Plugin;
+
+/// Merge of a plugin list into the output plugin, same as a single "--merge" list.
+pub struct MergeJob {
+    name: String,
+    plugins: Vec<String>,
+    list_options: ListOptions,
+    write_log: bool,
+}
+
+/// Result of [`MergeJob::run`].
+#[non_exhaustive]
+pub struct MergeResult {
+    /// Output plugin. It's empty if the list was skipped, e.g. due to "incremental" option.
+    pub plugin: Plugin,
+    /// Stats of the list, displayed the same way as with --verbose.
+    pub stats: Stats,
+    /// Warnings, e.g. merge conflicts, land seams, excluded records and ignored errors.
+    pub warnings: Vec<Warning>,
+}
+
+impl MergeJob {
+    /// Output plugin name may be provided as a path, plugins are provided the same way as in "--merge" list.
+    #[must_use]
+    pub fn new<S: Into<String>>(name: S, plugins: Vec<String>, list_options: ListOptions) -> Self {
+        Self {
+            name: name.into(),
+            plugins,
+            list_options,
+            write_log: false,
+        }
+    }
+
+    /// Write log file from settings, it's truncated and previous log file is backed up the same way as by command line program.
+    #[must_use]
+    pub const fn with_log_file(mut self) -> Self {
+        self.write_log = true;
+        self
+    }
+
+    /// Merge plugins. Output plugin is written unless "dry_run" list option is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if any plugin fails to be processed or output plugin fails to be written.
+    pub fn run(&self, cfg: &Cfg) -> Result<MergeResult> {
+        let mut log = if self.write_log {
+            Log::new(cfg)?
+        } else {
+            Log {
+                buffer: None,
+                warnings: None,
+            }
+        };
+        log.warnings = Some(Vec::new());
+        let mut list = Vec::with_capacity(self.plugins.len().saturating_add(1));
+        list.push(self.name.clone());
+        list.extend(self.plugins.iter().cloned());
+        let mut h = Helper::new();
+        let mut plugin = Plugin::new();
+        let mut old_plugin = Plugin::new();
+        process_list_with_options(
+            &list,
+            1,
+            self.list_options.clone(),
+            &mut plugin,
+            &mut old_plugin,
+            &mut h,
+            cfg,
+            &mut log,
+        )
+        .with_context(|| format!("Failed to process list \"{}\"", list.join(", ")))?;
+        Ok(MergeResult {
+            plugin,
+            stats: take(&mut h.g.stats),
+            warnings: log.warnings.unwrap_or_default(),
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::MergeJob;
+    use crate::{get_test_cfg_and_log, WarningKind};
+    use fs_err::{create_dir_all, remove_dir_all};
+    use std::{env::temp_dir, path::Path, process};
+    use tes3::esp::{Dialogue, DialogueType2, Header, Plugin, Static, TES3Object};
+
+    fn save_plugin(path: &Path, stat_id: &str, dialogue_type: DialogueType2) {
+        let mut plugin = Plugin::new();
+        plugin.objects = vec![
+            TES3Object::Header(Header {
+                num_objects: 2,
+                ..Default::default()
+            }),
+            TES3Object::Static(Static {
+                id: stat_id.to_owned(),
+                mesh: "test\\test.nif".to_owned(),
+                ..Default::default()
+            }),
+            TES3Object::Dialogue(Dialogue {
+                id: "test_topic".to_owned(),
+                dialogue_type,
+                ..Default::default()
+            }),
+        ];
+        plugin.save_path(path).expect("failed to save test plugin");
+    }
+
+    #[test]
+    fn run_returns_output_plugin_and_warnings() {
+        let (cfg, _) = get_test_cfg_and_log();
+        let list_options = cfg
+            .parse_list_options(&["dry_run"])
+            .expect("failed to parse list options");
+        let dir = temp_dir().join(format!("habasi_api_test_{}", process::id()));
+        create_dir_all(&dir).expect("failed to create test directory");
+        let plugin_1 = dir.join("Plugin1.esp");
+        let plugin_2 = dir.join("Plugin2.esp");
+        save_plugin(&plugin_1, "test_static_1", DialogueType2::Topic);
+        save_plugin(&plugin_2, "test_static_2", DialogueType2::Journal);
+        let plugins = vec![
+            plugin_1.to_string_lossy().into_owned(),
+            plugin_2.to_string_lossy().into_owned(),
+        ];
+        let result = MergeJob::new(
+            dir.join("MergedPlugin.esp").to_string_lossy(),
+            plugins,
+            list_options,
+        )
+        .run(&cfg);
+        remove_dir_all(&dir).expect("failed to remove test directory");
+        let result = result.expect("failed to run merge job");
+        let stat_ids = result
+            .plugin
+            .objects
+            .iter()
+            .filter_map(|object| {
+                if let TES3Object::Static(ref stat) = *object {
+                    Some(stat.id.as_str())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stat_ids,
+            ["test_static_1", "test_static_2"],
+            "output plugin doesn't contain records of both plugins"
+        );
+        assert!(
+            result
+                .warnings
+                .iter()
+                .any(|warning| warning.kind == WarningKind::DialogueTypeChange),
+            "dialogue type change wasn't reported as a warning"
+        );
+    }
+}
@@ -1,8 +1,16 @@
-use crate::{create_dir_early, IndirectListOptions, ListOptions};
-use anyhow::{Context as _, Result};
+use crate::{
+    check_grass_density, create_dir_early, parse_grass_exclude_grids, parse_grass_exclude_meshes,
+    parse_grass_exclude_regions, IndirectListOptions, ListOptions, Log,
+};
+use anyhow::{anyhow, Context as _, Result};
 use fs_err::write;
 use hashbrown::{HashMap, HashSet};
-use std::{ffi::OsString, path::PathBuf};
+use regex::Regex;
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+};
+use tes3::esp::GameSettingValue;
 mod options;
 mod settings;
 mod util;
@@ -11,13 +19,16 @@ use options::{get_options, Options};
 use settings::{get_settings, Settings};
 use util::{
     backup_settings_file, check_base_dir, check_mode, get_exe_name_and_dir, get_lists,
-    get_log_file, get_settings_file, make_keep_only_last_info_ids, make_tng_stat_ids,
-    prepare_plugin_extensions_to_ignore, set_low_string_osstring, set_new_name_retries,
+    get_log_file, get_settings_file, make_evil_gmsts, make_keep_only_last_info_ids,
+    make_record_rules, make_tng_stat_ids, prepare_plugin_extensions_to_ignore,
+    set_low_string_osstring, set_new_name_retries,
 };
 
 #[allow(clippy::struct_excessive_bools)]
 pub struct Cfg {
     pub(crate) merge: Vec<Vec<String>>,
+    pub(crate) unmerge: Option<String>,
+    pub(crate) diff: Option<Vec<String>>,
     pub(crate) log: Option<PathBuf>,
     pub(crate) no_log: bool,
     pub(crate) settings_file: SettingsFile,
@@ -25,16 +36,29 @@ pub struct Cfg {
     pub(crate) verbose: u8,
     pub(crate) quiet: bool,
     pub(crate) show_plugins: bool,
+    pub(crate) plugin_cache: PluginCache,
     pub(crate) list_options: ListOptions,
     pub(crate) presets: Presets,
     pub(crate) advanced: Advanced,
     pub(crate) guts: Guts,
 }
 
+pub struct PluginCache {
+    pub(crate) enabled: bool,
+    pub(crate) clear: bool,
+    pub(crate) dir: PathBuf,
+}
+
 pub struct Advanced {
     pub(crate) grass_filter: Vec<String>,
     pub(crate) turn_normal_grass_stat_ids: TngStatIds,
     pub(crate) keep_only_last_info_ids: HashMap<String, HashMap<String, String>>,
+    pub(crate) pathgrid_point_tolerance: i32,
+    pub(crate) pathgrid_static_distance: i32,
+    pub(crate) duplicate_refs_distance: f32,
+    pub(crate) duplicate_refs_rotation: f32,
+    pub(crate) record_rules: RecordRules,
+    pub(crate) evil_gmsts: HashMap<String, GameSettingValue>,
 }
 
 pub struct Guts {
@@ -62,6 +86,8 @@ pub struct Guts {
     pub(crate) plugin_extensions_to_ignore: Vec<String>,
     pub(crate) unexpected_tags_to_ignore: Vec<String>,
     pub(crate) skipped_processing_plugins_msg_verbosity: u8,
+    pub(crate) plugins_parsing_window: usize,
+    pub(crate) plugin_cache_dir: String,
     // [Section: "Hidden" OpenMW-CS data directory]
     pub(crate) omw_cs_data_path_suffix_linux_macos: String,
     pub(crate) omw_cs_data_path_suffix_windows: String,
@@ -84,11 +110,22 @@ pub struct Guts {
     pub(crate) header_description_merged_many_plugins_suffix: String,
     pub(crate) header_description_processed_one_plugin_prefix: String,
     pub(crate) header_description_processed_one_plugin_suffix: String,
+    pub(crate) header_description_unmerged_prefix: String,
+    pub(crate) header_description_unmerged_suffix: String,
     // [Section: Backup files suffixes]
     pub(crate) log_backup_suffix: String,
-    // [Section: Prefixes of per list options that take paths]
+    pub(crate) ref_map_suffix: String,
+    pub(crate) provenance_suffix: String,
+    pub(crate) unmerge_dir_suffix: String,
+    pub(crate) diff_report_suffix: String,
+    pub(crate) manifest_suffix: String,
+    // [Section: Prefixes of per list options that take values]
     pub(crate) list_options_prefix_base_dir: String,
     pub(crate) list_options_prefix_config: String,
+    pub(crate) list_options_prefix_grass_density: String,
+    pub(crate) list_options_prefix_grass_exclude_regions: String,
+    pub(crate) list_options_prefix_grass_exclude_grids: String,
+    pub(crate) list_options_prefix_grass_exclude_meshes: String,
     pub(crate) list_options_prefix_append_to_use_load_order: String,
     pub(crate) list_options_prefix_skip_from_use_load_order: String,
     // [Section: Messages]
@@ -124,7 +161,79 @@ pub struct TngStatIds {
     pub(crate) source_map: HashMap<String, String>,
 }
 
+pub struct RecordRules {
+    pub(crate) ids: HashMap<String, HashMap<String, RecordRule>>,
+    pub(crate) patterns: Vec<(String, Regex, RecordRule)>,
+}
+
+pub struct RecordRule {
+    pub(crate) action: RecordRuleAction,
+    pub(crate) reason: String,
+}
+
+pub enum RecordRuleAction {
+    Exclude,
+    KeepPrevious,
+    Winner(String),
+}
+
+impl RecordRules {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ids.is_empty() && self.patterns.is_empty()
+    }
+
+    pub(crate) fn get(&self, record_type: &str, id_low: &str) -> Option<&RecordRule> {
+        self.ids
+            .get(record_type)
+            .and_then(|ids| ids.get(id_low))
+            .or_else(|| {
+                self.patterns
+                    .iter()
+                    .find(|&&(ref pattern_type, ref pattern, _)| {
+                        pattern_type == record_type && pattern.is_match(id_low)
+                    })
+                    .map(|&(_, _, ref rule)| rule)
+            })
+    }
+}
+
 impl Cfg {
+    /// Configure the same way as the program does, i.e. from command line arguments and settings file. First argument is the program name.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if arguments or settings file are invalid.
+    pub fn from_args<I, T>(args: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        get_from(args)
+    }
+
+    /// Get list options from global options and per list options, e.g. `["replace", "dry_run"]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if any of the options is unknown or invalid.
+    pub fn parse_list_options<S: AsRef<str>>(&self, options: &[S]) -> Result<ListOptions> {
+        let mut list = Vec::with_capacity(options.len().saturating_add(1));
+        list.push(String::new());
+        list.extend(options.iter().map(|option| option.as_ref().to_owned()));
+        let mut log = Log {
+            buffer: None,
+            warnings: None,
+        };
+        let (index, list_options) = self
+            .list_options
+            .get_mutated(&list, self, &mut log)
+            .with_context(|| "Failed to get list options")?;
+        list.get(index).map_or_else(
+            || Ok(list_options),
+            |unknown| Err(anyhow!("Unknown list option {unknown:?}")),
+        )
+    }
+
     #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
     fn new(
         opt: Options,
@@ -160,9 +269,20 @@ impl Cfg {
         let preset_check_references = opt_or_set_bool!(preset_check_references);
         let preset_merge_load_order = opt_or_set_bool!(preset_merge_load_order);
         let preset_turn_normal_grass = opt_or_set_bool!(preset_turn_normal_grass);
+        let plugin_cache = PluginCache {
+            enabled: opt_or_set_bool!(plugin_cache),
+            clear: opt.clear_plugin_cache,
+            dir: settings_file
+                .path
+                .parent()
+                .map_or_else(PathBuf::new, Path::to_path_buf)
+                .join(&set.guts.plugin_cache_dir),
+        };
         Ok(Self {
             merge: get_lists(opt.merge, set.options.merge, opt.arguments_tail)
                 .with_context(|| "Failed to parse --merge lists")?,
+            unmerge: opt.unmerge,
+            diff: opt.diff,
             grass: opt_or_set_bool!(grass),
             no_log,
             log: get_log_file(no_log, &opt_or_set_some!(log), exe, dir)?,
@@ -193,6 +313,37 @@ impl Cfg {
                 force_dial_type: opt_or_set_bool!(force_dial_type),
                 insufficient_merge: opt_or_set_bool!(insufficient_merge),
                 dry_run_dismiss_stats: opt_or_set_bool!(dry_run_dismiss_stats),
+                merge_leveled_lists: opt_or_set_bool!(merge_leveled_lists),
+                merge_actors: opt_or_set_bool!(merge_actors),
+                merge_inventories: opt_or_set_bool!(merge_inventories),
+                merge_races_birthsigns: opt_or_set_bool!(merge_races_birthsigns),
+                merge_lands: opt_or_set_bool!(merge_lands),
+                check_land_seams: opt_or_set_bool!(check_land_seams),
+                stitch_land_seams: opt_or_set_bool!(stitch_land_seams),
+                merge_pathgrids: opt_or_set_bool!(merge_pathgrids),
+                repair_info_chains: opt_or_set_bool!(repair_info_chains),
+                clean_itm: opt_or_set_bool!(clean_itm),
+                exclude_evil_gmsts: opt_or_set_bool!(exclude_evil_gmsts),
+                check_duplicate_refs: opt_or_set_bool!(check_duplicate_refs),
+                exclude_duplicate_refs: opt_or_set_bool!(exclude_duplicate_refs),
+                stable_ref_indices: opt_or_set_bool!(stable_ref_indices),
+                write_provenance: opt_or_set_bool!(write_provenance),
+                write_ref_map: opt_or_set_bool!(write_ref_map),
+                write_diff_report: opt_or_set_bool!(write_diff_report),
+                incremental: opt_or_set_bool!(incremental),
+                grass_density: check_grass_density(opt_or_set_some!(grass_density))
+                    .with_context(|| "Failed to get default grass_density")?,
+                grass_exclude_regions: parse_grass_exclude_regions(&opt_or_set_some!(
+                    grass_exclude_regions
+                )),
+                grass_exclude_grids: parse_grass_exclude_grids(&opt_or_set_some!(
+                    grass_exclude_grids
+                ))
+                .with_context(|| "Failed to get default grass_exclude_grids")?,
+                grass_exclude_meshes: parse_grass_exclude_meshes(&opt_or_set_some!(
+                    grass_exclude_meshes
+                ))
+                .with_context(|| "Failed to get default grass_exclude_meshes")?,
                 append_to_use_load_order: opt_or_set_some!(append_to_use_load_order),
                 skip_from_use_load_order: opt_or_set_some!(skip_from_use_load_order),
                 indirect: IndirectListOptions::default(),
@@ -204,6 +355,7 @@ impl Cfg {
             },
             quiet: opt_or_set_bool!(quiet),
             show_plugins: opt_or_set_bool!(show_plugins),
+            plugin_cache,
             presets: Presets {
                 present: preset_check_references
                     || preset_merge_load_order
@@ -221,6 +373,12 @@ impl Cfg {
                 keep_only_last_info_ids: make_keep_only_last_info_ids(
                     set.advanced.keep_only_last_info_ids,
                 )?,
+                pathgrid_point_tolerance: set.advanced.pathgrid_point_tolerance,
+                pathgrid_static_distance: set.advanced.pathgrid_static_distance,
+                duplicate_refs_distance: set.advanced.duplicate_refs_distance,
+                duplicate_refs_rotation: set.advanced.duplicate_refs_rotation,
+                record_rules: make_record_rules(set.advanced.record_rules)?,
+                evil_gmsts: make_evil_gmsts(set.advanced.evil_gmsts)?,
             },
             guts: Guts {
                 // [Section: Presets]
@@ -262,6 +420,8 @@ impl Cfg {
                 skipped_processing_plugins_msg_verbosity: set
                     .guts
                     .skipped_processing_plugins_msg_verbosity,
+                plugins_parsing_window: set.guts.plugins_parsing_window,
+                plugin_cache_dir: set.guts.plugin_cache_dir,
                 // [Section: "Hidden" OpenMW-CS data directory]
                 omw_cs_data_path_suffix_linux_macos: set.guts.omw_cs_data_path_suffix_linux_macos,
                 omw_cs_data_path_suffix_windows: set.guts.omw_cs_data_path_suffix_windows,
@@ -304,11 +464,28 @@ impl Cfg {
                 header_description_processed_one_plugin_suffix: set
                     .guts
                     .header_description_processed_one_plugin_suffix,
+                header_description_unmerged_prefix: set.guts.header_description_unmerged_prefix,
+                header_description_unmerged_suffix: set.guts.header_description_unmerged_suffix,
                 // [Section: Backup files suffixes]
                 log_backup_suffix: set.guts.log_backup_suffix,
-                // [Section: Prefixes of per list options that take paths]
+                ref_map_suffix: set.guts.ref_map_suffix,
+                provenance_suffix: set.guts.provenance_suffix,
+                unmerge_dir_suffix: set.guts.unmerge_dir_suffix,
+                diff_report_suffix: set.guts.diff_report_suffix,
+                manifest_suffix: set.guts.manifest_suffix,
+                // [Section: Prefixes of per list options that take values]
                 list_options_prefix_base_dir: set.guts.list_options_prefix_base_dir,
                 list_options_prefix_config: set.guts.list_options_prefix_config,
+                list_options_prefix_grass_density: set.guts.list_options_prefix_grass_density,
+                list_options_prefix_grass_exclude_regions: set
+                    .guts
+                    .list_options_prefix_grass_exclude_regions,
+                list_options_prefix_grass_exclude_grids: set
+                    .guts
+                    .list_options_prefix_grass_exclude_grids,
+                list_options_prefix_grass_exclude_meshes: set
+                    .guts
+                    .list_options_prefix_grass_exclude_meshes,
                 list_options_prefix_append_to_use_load_order: set
                     .guts
                     .list_options_prefix_append_to_use_load_order,
@@ -324,7 +501,15 @@ impl Cfg {
 }
 
 pub fn get() -> Result<Cfg> {
-    let options = get_options()?;
+    get_from(wild::args_os())
+}
+
+pub fn get_from<I, T>(args: I) -> Result<Cfg>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let options = get_options(args)?;
     let (exe, dir) = get_exe_name_and_dir();
     let mut settings_file = get_settings_file(&exe, &dir, &options)
         .with_context(|| "Failed to get program settings file path")?;
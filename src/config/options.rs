@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::Parser;
+use std::ffi::OsString;
 mod help_option;
 use help_option::check_show_help_for_option;
 
@@ -128,6 +129,51 @@ pub(in crate::config) struct Options {
         help = "Process grass lists(enabled by default)"
     )]
     pub(super) grass: bool,
+    /// Cache parsed plugins in "habasi.cache" directory next to the settings file.
+    ///
+    /// Parsing large plugins is often the slowest part of merging. With this option every parsed plugin is saved into the cache and is loaded from it next time, unless plugin's size or modification time changed. Cache is also invalidated by a program version change. Each plugin path has a single cache file that is overwritten when the plugin changes, so cache only grows with new plugin paths. Use --clear-plugin-cache to remove it.
+    #[arg(
+        conflicts_with = "settings_write",
+        long,
+        alias = "plugin_cache",
+        help = "Cache parsed plugins to speed up next runs"
+    )]
+    pub(super) plugin_cache: bool,
+    /// Remove cache of parsed plugins before processing.
+    #[arg(
+        conflicts_with = "settings_write",
+        long,
+        alias = "clear_plugin_cache",
+        help = "Remove cache of parsed plugins"
+    )]
+    pub(super) clear_plugin_cache: bool,
+    /// Split plugin merged with "write_provenance" list option back into source plugins and exit.
+    ///
+    /// Provenance file "<PLUGIN>.provenance.toml" is required. Source plugins are written into "<PLUGIN>.unmerged" directory with original masters and reference indices restored. Each record goes to the plugin that won it during merge. Merged cells are split so that each plugin gets back own references, while references to masters go to the cell's winner.
+    ///
+    /// Records are restored as they were in the merged plugin, i.e. merged leveled lists, lands or pathgrids are not split into original versions. Use --dry-run to only show what would be written.
+    #[arg(
+        conflicts_with_all = ["settings_write", "merge"],
+        long,
+        value_name = "PLUGIN",
+        value_hint = clap::ValueHint::FilePath,
+        help = "Split merged plugin back into source plugins"
+    )]
+    pub(super) unmerge: Option<String>,
+    /// Show difference between two plugins and exit.
+    ///
+    /// Any two TES3 plugins may be compared, e.g. two versions of a mod. Records are matched by record type and ID, references are matched by master index and reference index within their cells. Every added, removed and changed record is listed grouped by record type, and every added, removed, changed or moved reference is listed grouped by cell. References with only position or rotation changed are reported as moved, NaN coordinates are treated as zeroes.
+    ///
+    /// Example: habasi --diff "Mod 1.0.esp" "Mod 1.1.esp"
+    #[arg(
+        conflicts_with_all = ["settings_write", "merge", "unmerge"],
+        long,
+        num_args = 2,
+        value_names = ["OLD", "NEW"],
+        value_hint = clap::ValueHint::FilePath,
+        help = "Show difference between two plugins"
+    )]
+    pub(super) diff: Option<Vec<String>>,
     /// Print help for specific option. Accepts both short and long option names.
     ///
     /// Long help(--help) is very long. Combining short help(-h) and this option(-?) is a convenient alternative.
@@ -636,6 +682,314 @@ pub(in crate::config) struct Options {
         help = "Process only cell references(and statics with '-M grass' or '-t')"
     )]
     pub(super) insufficient_merge: bool,
+    /// Merge leveled lists in Relev/Delev style.
+    ///
+    /// By default leveled lists(LEVI, LEVC) are either stacked in the output plugin for external leveled list mergers or simply replaced("complete_replace" mode). This option makes the program combine all versions of the list into one instead. First encountered version of the list is used as a base. Entries added by any of the following versions are added, entries removed by any of them are removed. Entries are compared by ID and level, e.g. the same item on different levels are different entries. Chance none and list flags are taken from the last version that changed them.
+    ///
+    /// Previous versions of merged lists are not placed into the output plugin(unless --debug is used), because there is nothing left to merge for external utilities.
+    ///
+    /// Corresponding per list options: "merge_leveled_lists", "no_merge_leveled_lists".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_leveled_lists",
+        help = "Merge leveled lists in Relev/Delev style"
+    )]
+    pub(super) merge_leveled_lists: bool,
+    /// Merge NPC and creature records(NPC_, CREA) field by field.
+    ///
+    /// By default the last version of the record replaces all the previous ones, so that one plugin's inventory change and another plugin's AI change to the same NPC can't both survive. This option makes the program perform three-way merge instead. First encountered version of the record is used as a base. Each group of fields(flags, stats, AI packages, inventory, spells, travel destinations, the rest of the fields) is taken from the last version that changed it. Conflicts(the same group of fields changed differently by multiple versions) are reported.
+    ///
+    /// Previous versions of merged records are not placed into the output plugin(unless --debug is used).
+    ///
+    /// Corresponding per list options: "merge_actors", "no_merge_actors".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_actors",
+        help = "Merge NPC and creature records field by field"
+    )]
+    pub(super) merge_actors: bool,
+    /// Merge inventory, spell and travel destination lists of containers, NPCs and creatures(CONT, NPC_, CREA).
+    ///
+    /// By default the last version of the record replaces the whole list, so that items added to the same shop container by different plugins clobber each other. This option makes the program merge lists while processing plugins instead. Every version of the record is compared to the first encountered version: entries added by it are added, entries removed by it are removed and item counts changed by it are changed.
+    ///
+    /// Corresponding per list options: "merge_inventories", "no_merge_inventories".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_inventories",
+        help = "Merge inventory, spell and travel destination lists"
+    )]
+    pub(super) merge_inventories: bool,
+    /// Merge races and birthsigns(RACE, BSGN).
+    ///
+    /// By default the last version of the record replaces all the previous ones, so that racial abilities added by one plugin are lost when another plugin changes the same race. This option makes the program unite spell lists of all versions instead, spells of the first encountered version go first and spells added by later versions are appended. Skill bonuses, attributes and the rest of the fields are taken from the last version that changed them compared to the first encountered version.
+    ///
+    /// Previous versions of merged records are not placed into the output plugin(unless --debug is used).
+    ///
+    /// Corresponding per list options: "merge_races_birthsigns", "no_merge_races_birthsigns".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_races_birthsigns",
+        help = "Merge spell lists of races and birthsigns"
+    )]
+    pub(super) merge_races_birthsigns: bool,
+    /// Merge landscape records(LAND) subrecord by subrecord.
+    ///
+    /// By default the last version of the record replaces all the previous ones, so that a texture-only retexture plugin undoes height map fix from another plugin. This option makes the program compare each subrecord(vertex heights, vertex normals, vertex colors, texture indices, world map data) of every version to the first encountered version instead. Each subrecord is taken from the last version that changed it. Cells where the same subrecord was changed differently by multiple versions are reported.
+    ///
+    /// Previous versions of merged records are not placed into the output plugin(unless --debug is used).
+    ///
+    /// Corresponding per list options: "merge_lands", "no_merge_lands".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_lands",
+        help = "Merge landscape records subrecord by subrecord"
+    )]
+    pub(super) merge_lands: bool,
+    /// Report seams between adjacent exterior landscape records(LAND) of the output plugin.
+    ///
+    /// Merging landscape records from different landmass and patch plugins may leave neighbouring cells with different heights or normals of shared edge vertices, which shows up as visible tears. This option makes the program compare edges of adjacent cells after merging and report every seam found.
+    ///
+    /// Corresponding per list options: "check_land_seams", "no_check_land_seams".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "check_land_seams",
+        help = "Report seams between adjacent landscape records"
+    )]
+    pub(super) check_land_seams: bool,
+    /// Stitch seams between adjacent exterior landscape records(LAND) of the output plugin. Implies --check-land-seams.
+    ///
+    /// Heights and normals of shared edge vertices are set to the average of both cells. Seams that can't be stitched without breaking height map encoding(too steep slope next to the edge) are left as is and reported. May make "keep_without_lands" style workarounds unnecessary for many setups.
+    ///
+    /// Corresponding per list options: "stitch_land_seams", "no_stitch_land_seams".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "stitch_land_seams",
+        help = "Stitch seams between adjacent landscape records"
+    )]
+    pub(super) stitch_land_seams: bool,
+    /// Merge pathgrid records(PGRD) by uniting points and connections from all plugins.
+    ///
+    /// By default the last version of the record replaces all the previous ones, so that references added by another plugin leave actors walking through walls. This option makes the program unite pathgrids instead. Points closer than settings.advanced.pathgrid_point_tolerance to already existing points are treated as the same point, connections are reindexed accordingly. Points that end up next to newly added static references may be reported with settings.advanced.pathgrid_static_distance.
+    ///
+    /// Corresponding per list options: "merge_pathgrids", "no_merge_pathgrids".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "merge_pathgrids",
+        help = "Merge pathgrid points and connections"
+    )]
+    pub(super) merge_pathgrids: bool,
+    /// Repair broken links between dialogue info records(INFO) of merged topics.
+    ///
+    /// Info records of each topic are linked into chain with previous and next info ids. The program always checks the final chain of each topic and reports dangling, inconsistent and cyclic links with the plugin that caused them, because broken chains make dialogue silently disappear in Morrowind.exe. This option makes the program rebuild links of problematic topics from the merged insertion order. Links to info records that are not present in the output plugin are treated as links to master files when the output plugin has masters.
+    ///
+    /// Corresponding per list options: "repair_info_chains", "no_repair_info_chains".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "repair_info_chains",
+        help = "Repair broken INFO chains of merged dialogue"
+    )]
+    pub(super) repair_info_chains: bool,
+    /// Remove records and references identical to their master files.
+    ///
+    /// Plugins often contain records that are exactly the same as records of their master files(identical to master, ITM), e.g. due to accidental edits in the Construction Set. Such records do nothing useful, though they revert changes made by other plugins loaded in between. This option makes the program read master files of merged plugins, count records and references identical to master per plugin, and remove records and references of the output plugin that end up identical to master. Cells are removed when nothing but references identical to master was changed in them. Dialogue records(DIAL, INFO) are not cleaned. Master files are searched in the game load order(when --use-load-order is used), directory of the plugin and base_dir.
+    ///
+    /// Corresponding per list options: "clean_itm", "no_clean_itm".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "clean_itm",
+        help = "Remove records identical to master files"
+    )]
+    pub(super) clean_itm: bool,
+    /// Exclude game setting records(GMST) that have vanilla default values("evil GMSTs").
+    ///
+    /// Plugins made with the original Construction Set often contain dozens of game settings with vanilla default values. They silently revert changes of game settings made by other plugins once merged. The program always reports game settings of the output plugin with values from settings.advanced.evil_gmsts, unless there is a rule for them in settings.advanced.record_rules. This option makes the program exclude them from the output plugin.
+    ///
+    /// Corresponding per list options: "exclude_evil_gmsts", "no_exclude_evil_gmsts".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "exclude_evil_gmsts",
+        help = "Exclude GMST records with vanilla default values"
+    )]
+    pub(super) exclude_evil_gmsts: bool,
+    /// Report duplicate references in merged cells.
+    ///
+    /// When several plugins place the same object at nearly the same position, the merged cell ends up with stacked duplicates. It results in z-fighting, duplicate loot and doubled density of grass when overlapping grass mods are merged. This option makes the program report references with the same object ID, which positions and rotations differ by no more than settings.advanced.duplicate_refs_distance and settings.advanced.duplicate_refs_rotation. Duplicates are grouped by cell and reported with plugins they came from.
+    ///
+    /// Corresponding per list options: "check_duplicate_refs", "no_check_duplicate_refs".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "check_duplicate_refs",
+        help = "Report duplicate references in merged cells"
+    )]
+    pub(super) check_duplicate_refs: bool,
+    /// Exclude duplicate references in merged cells. Implicitly sets --check-duplicate-refs.
+    ///
+    /// Only one reference of each group of duplicates is kept. References from master files are never excluded, so duplicates of them are excluded instead. Otherwise the reference from the plugin that comes first in the list is kept.
+    ///
+    /// Corresponding per list options: "exclude_duplicate_refs", "no_exclude_duplicate_refs".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "exclude_duplicate_refs",
+        help = "Exclude duplicate references in merged cells"
+    )]
+    pub(super) exclude_duplicate_refs: bool,
+    /// Keep reference indices from previous output plugin.
+    ///
+    /// References are numbered anew on each run by default, so adding or removing a plugin renumbers references of the output plugin and new game is recommended. This option makes the program write reference map next to the output plugin, i.e. "MergedPlugin.esp.refmap.toml". Map links each reference of the output plugin to its source plugin and original index. On the next run references found in the map keep their previous indices, while new references get fresh indices above the highest index used by the previous output plugin. It allows to update merged plugins mid-playthrough. Map entries are only trusted when previous output plugin exists and still contains their references. Takes precedence over --reindex.
+    ///
+    /// Corresponding per list options: "stable_ref_indices", "no_stable_ref_indices".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "stable_ref_indices",
+        help = "Keep reference indices from previous output plugin"
+    )]
+    pub(super) stable_ref_indices: bool,
+    /// Write provenance file next to the output plugin.
+    ///
+    /// Provenance file is written next to the output plugin, i.e. "MergedPlugin.esp.provenance.toml". It lists the winning plugin and overridden plugins for each record encountered, and the source plugin with original index for each cell reference of the output plugin. Winning plugin is the last plugin whose version of the record was taken, so records merged from multiple plugins, e.g. with --merge-leveled-lists, list the rest of contributing plugins as overridden. It's meant to help with investigation of issues in merged setups.
+    ///
+    /// Corresponding per list options: "write_provenance", "no_write_provenance".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "write_provenance",
+        help = "Write provenance file next to the output plugin"
+    )]
+    pub(super) write_provenance: bool,
+    /// Write reference map next to the output plugin.
+    ///
+    /// Reference map is written next to the output plugin, i.e. "MergedPlugin.esp.refmap.toml". It links original index of each reference of every source plugin to the index of the same reference in the output plugin, so external tools like save editors may fix saves made with source plugins or with previous version of the output plugin. Map is always written with --stable-ref-indices, this option makes it written without it too. See README for format description.
+    ///
+    /// Corresponding per list options: "write_ref_map", "no_write_ref_map".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "write_ref_map",
+        help = "Write reference map next to the output plugin"
+    )]
+    pub(super) write_ref_map: bool,
+    /// Write full diff against previous output plugin.
+    ///
+    /// Output plugin is compared with previous version by default, though only the first difference is reported. This option makes the program write report file next to the output plugin, i.e. "MergedPlugin.esp.diff.txt", when plugins differ. Report lists every added, removed and changed record grouped by record type, and every added, removed, changed or moved reference grouped by cell. References with only position or rotation changed are reported as moved. Summary is shown and logged. Report is written even with --dry-run to allow reviewing changes before writing output plugin. Implicitly unsets --no-compare.
+    ///
+    /// Corresponding per list options: "write_diff_report", "no_write_diff_report".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "write_diff_report",
+        help = "Write full diff against previous output plugin"
+    )]
+    pub(super) write_diff_report: bool,
+    /// Skip list when its inputs didn't change since the previous run.
+    ///
+    /// Manifest of inputs is written next to the output plugin, i.e. "MergedPlugin.esp.manifest.toml". It contains paths, sizes and modification times of plugins to merge, settings file and the output plugin itself, together with list options and program version. List is skipped when the manifest matches, otherwise the reason of rebuilding the list is shown. Manifest file suffix may be changed in settings file.
+    ///
+    /// This option is implicitly unset with --clean-itm, --turn-normal-grass and --merge-pathgrids with non-zero settings.advanced.pathgrid_static_distance, because master files, meshes and BSA archives they read are not tracked by manifest.
+    ///
+    /// Corresponding per list options: "incremental", "no_incremental".
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "incremental",
+        help = "Skip list when its inputs didn't change since the previous run"
+    )]
+    pub(super) incremental: bool,
+    /// Keep only given percent of grass instances. This option would only be effective in grass mode.
+    ///
+    /// Instances are thinned deterministically per cell, so the same instances are kept on each run until plugins change. Value should be an integer from 0 to 100, where 100 means no thinning.
+    ///
+    /// Default value: 100(option turned off).
+    ///
+    /// Corresponding per list option: "grass_density:<PERCENT>", default value: "grass_density:100"(option turned off).
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "grass_density",
+        value_name = "PERCENT",
+        help = "Keep only given percent of grass instances"
+    )]
+    pub(super) grass_density: Option<u8>,
+    /// Exclude grass from exterior cells of given regions. This option would only be effective in grass mode.
+    ///
+    /// Region IDs are case insensitive and separated with "|", e.g. "Ascadian Isles Region|West Gash Region".
+    ///
+    /// Default value: ""(option turned off).
+    ///
+    /// Corresponding per list option: "grass_exclude_regions:<REGIONS>", default value: "grass_exclude_regions:"(option turned off).
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "grass_exclude_regions",
+        value_name = "REGIONS",
+        help = "Exclude grass from cells of given regions"
+    )]
+    pub(super) grass_exclude_regions: Option<String>,
+    /// Exclude grass from exterior cells within given grid rectangles. This option would only be effective in grass mode.
+    ///
+    /// Rectangles are defined by corner cells in format "X1:Y1:X2:Y2" and separated with "|", e.g. "-3:-10:0:-8|5:5:5:5".
+    ///
+    /// Default value: ""(option turned off).
+    ///
+    /// Corresponding per list option: "grass_exclude_grids:<GRIDS>", default value: "grass_exclude_grids:"(option turned off).
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "grass_exclude_grids",
+        value_name = "GRIDS",
+        help = "Exclude grass from cells within given grid rectangles"
+    )]
+    pub(super) grass_exclude_grids: Option<String>,
+    /// Exclude grass instances with mesh paths matching given patterns. This option would only be effective in grass mode.
+    ///
+    /// Patterns are case insensitive regular expressions separated with "|", e.g. "grass_fern|flora_bc_". Instances of statics defined in the merged plugins are checked.
+    ///
+    /// Default value: ""(option turned off).
+    ///
+    /// Corresponding per list option: "grass_exclude_meshes:<PATTERNS>", default value: "grass_exclude_meshes:"(option turned off).
+    #[arg(
+        help_heading = "List options",
+        conflicts_with = "settings_write",
+        long,
+        alias = "grass_exclude_meshes",
+        value_name = "PATTERNS",
+        help = "Exclude grass instances with matching mesh paths"
+    )]
+    pub(super) grass_exclude_meshes: Option<String>,
     /// Append plugin path to --use-load-order list. This option would only be effective combined with --use-load-order.
     ///
     /// It's made specifically for combination of -O and -T presets to allow adding newly created -GROUNDCOVER plugin into groundcover plugins list. May probably be used for similar tasks. Similar to --skip-from-use-load-order, though requires path to plugin instead of plugin name.
@@ -706,8 +1060,12 @@ pub(in crate::config) struct Options {
     pub(super) show_plugins: bool,
 }
 
-pub(in crate::config) fn get_options() -> Result<Options> {
-    let options = Options::try_parse_from(wild::args_os())?;
+pub(in crate::config) fn get_options<I, T>(args: I) -> Result<Options>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let options = Options::try_parse_from(args)?;
     check_show_help_for_option(&options)?;
     Ok(options)
 }
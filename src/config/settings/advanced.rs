@@ -70,4 +70,83 @@ pub struct Advanced {
     /// [keep_only_last_info_ids] Previous instance of the INFO record is removed when record with the same ID(and from the same topic) comes into a merged plugin. Format: ["ID", "Topic(case insensitive)", "Reason"].
     #[config(default = [["19511310302976825065", "threaten", "This record is problematic when coming from both LGNPC_GnaarMok and LGNPC_SecretMasters. I've failed to find the reason. Error in OpenMW-CS: \"Loading failed: attempt to change the ID of a record\"."]])]
     pub(crate) keep_only_last_info_ids: Vec<Vec<String>>,
+    /// [pathgrid_point_tolerance] Used with merge_pathgrids option. Pathgrid points of different plugins are treated as the same point when each of their coordinates differs by no more than this value.
+    #[config(default = 32)]
+    pub(crate) pathgrid_point_tolerance: i32,
+    /// [pathgrid_static_distance] Used with merge_pathgrids option. Pathgrid points closer than this value to the origin of static references added by merged plugins are reported, because they may end up inside of the static. Value 0 turns the check off.
+    #[config(default = 0)]
+    pub(crate) pathgrid_static_distance: i32,
+    /// [duplicate_refs_distance] Used with check_duplicate_refs option. References with the same object ID are treated as duplicates when each of their coordinates differs by no more than this value.
+    #[config(default = 1.0)]
+    pub(crate) duplicate_refs_distance: f32,
+    /// [duplicate_refs_rotation] Used with check_duplicate_refs option. References with the same object ID are treated as duplicates when each of their rotation angles differs by no more than this value(radians).
+    #[config(default = 0.01)]
+    pub(crate) duplicate_refs_rotation: f32,
+    /// [record_rules] Rules to pin contested records. Format: ["Type", "ID", "Action", "Reason"]. Type is record type(e.g. "NPC_", case insensitive). ID is case insensitive, prefix it with "regex:" to match IDs with regex pattern instead(e.g. "regex:^ttooth_"). Action is one of: "exclude"(record is excluded from the result), "keep_previous"(previous versions of the record are placed into the output plugin regardless of mode), "plugin:<PLUGIN_NAME>"(version of the record from the plugin wins as is, versions from the following plugins are ignored). Records with "keep_previous" or "plugin:<PLUGIN_NAME>" action are not merged by mergers(e.g. merge_leveled_lists, merge_pathgrids). Rules are not supported for CELL, LAND, LTEX, DIAL, INFO, SKIL and MGEF records.
+    #[config(default = [])]
+    pub(crate) record_rules: Vec<Vec<String>>,
+    /// [evil_gmsts] Game settings(GMST) that are often found in plugins with vanilla default values(i.e. "evil GMSTs") due to the original Construction Set. Such game settings revert changes made by other plugins. They are reported and may be excluded with exclude_evil_gmsts option. Format: ["ID", "Value"]. ID is case insensitive. Type of value is determined by the first letter of ID: "f" is float, "i" is integer, "s" is string.
+    #[config(default = [
+["fCombatDistanceWerewolfMod", "0.3"],
+["fFleeDistance", "3000"],
+["fWereWolfAcrobatics", "80"],
+["fWereWolfAgility", "150"],
+["fWereWolfAlchemy", "100"],
+["fWereWolfAlteration", "100"],
+["fWereWolfArmorer", "100"],
+["fWereWolfAthletics", "150"],
+["fWereWolfAxe", "100"],
+["fWereWolfBlock", "100"],
+["fWereWolfBluntWeapon", "100"],
+["fWereWolfConjuration", "100"],
+["fWereWolfDestruction", "100"],
+["fWereWolfEnchant", "100"],
+["fWereWolfEndurance", "150"],
+["fWereWolfFatigue", "400"],
+["fWereWolfHandtoHand", "100"],
+["fWereWolfHealth", "2"],
+["fWereWolfHeavyArmor", "100"],
+["fWereWolfIllusion", "100"],
+["fWereWolfIntellegence", "0.1"],
+["fWereWolfLightArmor", "100"],
+["fWereWolfLongBlade", "100"],
+["fWereWolfLuck", "0.1"],
+["fWereWolfMagicka", "100"],
+["fWereWolfMarksman", "100"],
+["fWereWolfMediumArmor", "100"],
+["fWereWolfMerchantile", "100"],
+["fWereWolfMysticism", "100"],
+["fWereWolfPersonality", "0.1"],
+["fWereWolfRestoration", "100"],
+["fWereWolfRunMult", "1.3"],
+["fWereWolfSecurity", "100"],
+["fWereWolfShortBlade", "100"],
+["fWereWolfSilverWeaponDamageMult", "1.5"],
+["fWereWolfSneak", "100"],
+["fWereWolfSpear", "100"],
+["fWereWolfSpeechcraft", "0.1"],
+["fWereWolfSpeed", "150"],
+["fWereWolfStrength", "150"],
+["fWereWolfUnarmored", "100"],
+["fWereWolfWillPower", "0.1"],
+["iWereWolfBounty", "10000"],
+["iWereWolfFightMod", "100"],
+["iWereWolfFleeMod", "100"],
+["iWereWolfLevelToAttack", "20"],
+["sCompanionShare", "Companion Share"],
+["sCompanionWarningButtonOne", "Let the mercenary quit."],
+["sCompanionWarningButtonTwo", "Return to Companion Share display."],
+["sCompanionWarningMessage", "Your mercenary is poorer now than when he contracted with you.  Your mercenary will quit if you do not give him gold or goods to bring his Profit Value to a positive value."],
+["sDeleteNote", "Delete Note?"],
+["sEditNote", "Edit Note"],
+["sLevitateDisabled", "Levitation magic does not work here."],
+["sMaxSale", "Max Sale"],
+["sProfitValue", "Profit Value"],
+["sTeleportDisabled", "Teleportation magic does not work here."],
+["sWerewolfAlarmMessage", "You have been detected changing from a werewolf state."],
+["sWerewolfPopup", "Werewolf"],
+["sWerewolfRefusal", "You cannot do this as a werewolf."],
+["sWerewolfRestMessage", "You cannot rest in werewolf form."],
+    ])]
+    pub(crate) evil_gmsts: Vec<Vec<String>>,
 }
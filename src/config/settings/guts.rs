@@ -6,7 +6,7 @@ pub struct Guts {
     /// Guts of the program. Use at your own risk ;-)
     ///
     /// # Following line is used to determine version of used settings to warn about outdated version:
-    /// # Settings version: 0.3.6
+    /// # Settings version: 0.3.9
     ///
     /// [Section: Presets]
     #[config(default = ["CheckReferences.esp", "dry_run", "use_load_order", "show_missing_refs", "complete_replace", "no_compare", "ignore_errors", "insufficient_merge", "dry_run_dismiss_stats"])]
@@ -65,6 +65,12 @@ pub struct Guts {
     pub(crate) unexpected_tags_to_ignore: Vec<String>,
     #[config(default = 1_u8)]
     pub(crate) skipped_processing_plugins_msg_verbosity: u8,
+    /// Number of plugins parsed in parallel ahead of processing. Plugins are still processed one by one in load order, so output is the same. Higher values take more memory, 1 disables parallel parsing.
+    #[config(default = 16)]
+    pub(crate) plugins_parsing_window: usize,
+    /// Directory of the cache of parsed plugins used with "plugin_cache" option. It's placed next to the settings file.
+    #[config(default = "habasi.cache")]
+    pub(crate) plugin_cache_dir: String,
     /// [Section: "Hidden" OpenMW-CS data directory]
     ///
     /// Path that is appended to the "data_dir": "$HOME/.local/share|$HOME/Library/Application Support" + omw_cs_data_path_suffix_linux_macos
@@ -123,16 +129,45 @@ pub struct Guts {
     pub(crate) header_description_processed_one_plugin_prefix: String,
     #[config(default = "\"")]
     pub(crate) header_description_processed_one_plugin_suffix: String,
+    /// Unmerged plugins would result in 'Unmerged from "MERGED_PLUGIN_PATH"'.
+    #[config(default = "Unmerged from \"")]
+    pub(crate) header_description_unmerged_prefix: String,
+    #[config(default = "\"")]
+    pub(crate) header_description_unmerged_suffix: String,
     /// [Section: Backup file suffixes]
     #[config(default = ".backup")]
     pub(crate) settings_backup_suffix: String,
     #[config(default = ".backup")]
     pub(crate) log_backup_suffix: String,
-    /// [Section: Prefixes of per list options that take paths]
+    /// [Section: Sidecar file suffixes]
+    /// Reference map is written next to the output plugin with "stable_ref_indices" or "write_ref_map" options, i.e. "MergedPlugin.esp.refmap.toml".
+    #[config(default = ".refmap.toml")]
+    pub(crate) ref_map_suffix: String,
+    /// Provenance file is written next to the output plugin with "write_provenance" option, i.e. "MergedPlugin.esp.provenance.toml".
+    #[config(default = ".provenance.toml")]
+    pub(crate) provenance_suffix: String,
+    /// Unmerged plugins are written into directory next to the merged plugin with --unmerge option, i.e. "MergedPlugin.esp.unmerged".
+    #[config(default = ".unmerged")]
+    pub(crate) unmerge_dir_suffix: String,
+    /// Diff report is written next to the output plugin with "write_diff_report" option, i.e. "MergedPlugin.esp.diff.txt".
+    #[config(default = ".diff.txt")]
+    pub(crate) diff_report_suffix: String,
+    /// Manifest of inputs is written next to the output plugin with "incremental" option, i.e. "MergedPlugin.esp.manifest.toml".
+    #[config(default = ".manifest.toml")]
+    pub(crate) manifest_suffix: String,
+    /// [Section: Prefixes of per list options that take values]
     #[config(default = "base_dir:")]
     pub(crate) list_options_prefix_base_dir: String,
     #[config(default = "config:")]
     pub(crate) list_options_prefix_config: String,
+    #[config(default = "grass_density:")]
+    pub(crate) list_options_prefix_grass_density: String,
+    #[config(default = "grass_exclude_regions:")]
+    pub(crate) list_options_prefix_grass_exclude_regions: String,
+    #[config(default = "grass_exclude_grids:")]
+    pub(crate) list_options_prefix_grass_exclude_grids: String,
+    #[config(default = "grass_exclude_meshes:")]
+    pub(crate) list_options_prefix_grass_exclude_meshes: String,
     #[config(default = "append_to_use_load_order:")]
     pub(crate) list_options_prefix_append_to_use_load_order: String,
     #[config(default = "skip_from_use_load_order:")]
@@ -57,6 +57,28 @@ pub struct Options {
     ///   - [force_dial_type] "no_force_dial_type", "force_dial_type"
     ///   - [ignore_important_errors] "no_ignore_important_errors", "ignore_important_errors"
     ///   - [insufficient_merge] "no_insufficient_merge", "insufficient_merge"
+    ///   - [merge_leveled_lists] "no_merge_leveled_lists", "merge_leveled_lists"
+    ///   - [merge_actors] "no_merge_actors", "merge_actors"
+    ///   - [merge_inventories] "no_merge_inventories", "merge_inventories"
+    ///   - [merge_races_birthsigns] "no_merge_races_birthsigns", "merge_races_birthsigns"
+    ///   - [merge_lands] "no_merge_lands", "merge_lands"
+    ///   - [check_land_seams] "no_check_land_seams", "check_land_seams"
+    ///   - [stitch_land_seams] "no_stitch_land_seams", "stitch_land_seams"
+    ///   - [merge_pathgrids] "no_merge_pathgrids", "merge_pathgrids"
+    ///   - [repair_info_chains] "no_repair_info_chains", "repair_info_chains"
+    ///   - [clean_itm] "no_clean_itm", "clean_itm"
+    ///   - [exclude_evil_gmsts] "no_exclude_evil_gmsts", "exclude_evil_gmsts"
+    ///   - [check_duplicate_refs] "no_check_duplicate_refs", "check_duplicate_refs"
+    ///   - [exclude_duplicate_refs] "no_exclude_duplicate_refs", "exclude_duplicate_refs"
+    ///   - [stable_ref_indices] "no_stable_ref_indices", "stable_ref_indices"
+    ///   - [write_provenance] "no_write_provenance", "write_provenance"
+    ///   - [write_ref_map] "no_write_ref_map", "write_ref_map"
+    ///   - [write_diff_report] "no_write_diff_report", "write_diff_report"
+    ///   - [incremental] "no_incremental", "incremental"
+    ///   - [grass_density] "grass_density:100", "grass_density:<PERCENT>"
+    ///   - [grass_exclude_regions] "grass_exclude_regions:", "grass_exclude_regions:<REGIONS>"
+    ///   - [grass_exclude_grids] "grass_exclude_grids:", "grass_exclude_grids:<GRIDS>"
+    ///   - [grass_exclude_meshes] "grass_exclude_meshes:", "grass_exclude_meshes:<PATTERNS>"
     ///   - [append_to_use_load_order] "append_to_use_load_order:", "append_to_use_load_order:<PATH>"
     ///   - [skip_from_use_load_order] "skip_from_use_load_order:", "skip_from_use_load_order:<NAME>"
     #[config(default = [])]
@@ -67,6 +89,8 @@ pub struct Options {
     pub(crate) no_log: bool,
     #[config(default = true)]
     pub(crate) grass: bool,
+    #[config(default = false)]
+    pub(crate) plugin_cache: bool,
     /// [--verbosity] Number corresponds to the number of verbose flags passed, e.g. -v = 1, -vv = 2, -vvv = 3
     #[config(default = 0_u8)]
     pub(crate) verbose: u8,
@@ -130,6 +154,50 @@ pub struct Options {
     pub(crate) ignore_important_errors: bool,
     #[config(default = false)]
     pub(crate) insufficient_merge: bool,
+    #[config(default = false)]
+    pub(crate) merge_leveled_lists: bool,
+    #[config(default = false)]
+    pub(crate) merge_actors: bool,
+    #[config(default = false)]
+    pub(crate) merge_inventories: bool,
+    #[config(default = false)]
+    pub(crate) merge_races_birthsigns: bool,
+    #[config(default = false)]
+    pub(crate) merge_lands: bool,
+    #[config(default = false)]
+    pub(crate) check_land_seams: bool,
+    #[config(default = false)]
+    pub(crate) stitch_land_seams: bool,
+    #[config(default = false)]
+    pub(crate) merge_pathgrids: bool,
+    #[config(default = false)]
+    pub(crate) repair_info_chains: bool,
+    #[config(default = false)]
+    pub(crate) clean_itm: bool,
+    #[config(default = false)]
+    pub(crate) exclude_evil_gmsts: bool,
+    #[config(default = false)]
+    pub(crate) check_duplicate_refs: bool,
+    #[config(default = false)]
+    pub(crate) exclude_duplicate_refs: bool,
+    #[config(default = false)]
+    pub(crate) stable_ref_indices: bool,
+    #[config(default = false)]
+    pub(crate) write_provenance: bool,
+    #[config(default = false)]
+    pub(crate) write_ref_map: bool,
+    #[config(default = false)]
+    pub(crate) write_diff_report: bool,
+    #[config(default = false)]
+    pub(crate) incremental: bool,
+    #[config(default = 100)]
+    pub(crate) grass_density: u8,
+    #[config(default = "")]
+    pub(crate) grass_exclude_regions: String,
+    #[config(default = "")]
+    pub(crate) grass_exclude_grids: String,
+    #[config(default = "")]
+    pub(crate) grass_exclude_meshes: String,
     #[config(default = "")]
     pub(crate) append_to_use_load_order: String,
     #[config(default = "")]
@@ -1,12 +1,16 @@
-use super::{Options, SettingsFile, StringOsPath, TngStatIds};
+use super::{
+    Options, RecordRule, RecordRuleAction, RecordRules, SettingsFile, StringOsPath, TngStatIds,
+};
 use crate::{increment, Mode};
 use anyhow::{anyhow, Context as _, Result};
 use fs_err::rename;
 use hashbrown::{hash_map::Entry, HashMap, HashSet};
+use regex::RegexBuilder;
 use std::{
     env::current_exe,
     path::{Path, PathBuf},
 };
+use tes3::esp::GameSettingValue;
 
 pub fn get_exe_name_and_dir() -> (Option<String>, Option<PathBuf>) {
     current_exe().map_or((None, None), |path| {
@@ -407,6 +411,140 @@ fn make_keep_only_last_info_ids_err_text(
     ))
 }
 
+// COMMENT: all actions apply to these types, rules for the rest(e.g. CELL, LAND, INFO) would be silently ignored, so they are rejected
+const RECORD_RULES_TYPES: [&str; 35] = [
+    "gmst", "glob", "clas", "fact", "race", "soun", "sndg", "scpt", "regn", "bsgn", "sscr", "spel",
+    "stat", "door", "misc", "weap", "cont", "crea", "body", "ligh", "ench", "npc_", "armo", "clot",
+    "repa", "acti", "appa", "lock", "prob", "ingr", "book", "alch", "levi", "levc", "pgrd",
+];
+
+pub fn make_record_rules(list: Vec<Vec<String>>) -> Result<RecordRules> {
+    let mut res = RecordRules {
+        ids: HashMap::new(),
+        patterns: Vec::new(),
+    };
+    for (n, line) in list.into_iter().enumerate() {
+        let line_len = line.len();
+        if !(3..=4).contains(&line_len) {
+            let description =
+                "Should contain 3 or 4 subelements [\"Type\", \"ID\", \"Action\", \"Reason\"]";
+            return Err(anyhow!(make_record_rules_err_text(description, n, &line)?));
+        }
+        let record_type = line
+            .first()
+            .context("Bug: unreachable due to 3 <= line_len <= 4")?
+            .to_lowercase();
+        if !RECORD_RULES_TYPES.contains(&&*record_type) {
+            let description = &format!(
+                "Type should be one of: {}",
+                RECORD_RULES_TYPES
+                    .iter()
+                    .map(|x| x.to_uppercase())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            return Err(anyhow!(make_record_rules_err_text(description, n, &line)?));
+        }
+        let id = line
+            .get(1)
+            .context("Bug: unreachable due to 3 <= line_len <= 4")?;
+        let action_raw = line
+            .get(2)
+            .context("Bug: unreachable due to 3 <= line_len <= 4")?;
+        let action = match &*action_raw.to_lowercase() {
+            "exclude" => RecordRuleAction::Exclude,
+            "keep_previous" => RecordRuleAction::KeepPrevious,
+            action_low => match action_low.strip_prefix("plugin:") {
+                Some(plugin_name_low) if !plugin_name_low.is_empty() => {
+                    RecordRuleAction::Winner(plugin_name_low.to_owned())
+                }
+                _ => {
+                    let description = "Action should be one of: \"exclude\", \"keep_previous\", \"plugin:<PLUGIN_NAME>\"";
+                    return Err(anyhow!(make_record_rules_err_text(description, n, &line)?));
+                }
+            },
+        };
+        let reason = if line_len == 4 {
+            line.get(3)
+                .context("Bug: unreachable due to 3 <= line_len <= 4")?
+                .clone()
+        } else {
+            String::from("Reason not defined.")
+        };
+        let rule = RecordRule { action, reason };
+        if let Some(pattern) = id.strip_prefix("regex:") {
+            let regex = match RegexBuilder::new(pattern).case_insensitive(true).build() {
+                Ok(regex) => regex,
+                Err(error) => {
+                    let description = &format!("ID regex pattern is invalid: {error}");
+                    return Err(anyhow!(make_record_rules_err_text(description, n, &line)?));
+                }
+            };
+            res.patterns.push((record_type, regex, rule));
+        } else if res
+            .ids
+            .entry(record_type)
+            .or_default()
+            .insert(id.to_lowercase(), rule)
+            .is_some()
+        {
+            let description = "There is already a rule for this pair of \"Type\" and \"ID\"";
+            return Err(anyhow!(make_record_rules_err_text(description, n, &line)?));
+        }
+    }
+    Ok(res)
+}
+
+fn make_record_rules_err_text(
+    description: &str,
+    line_num: usize,
+    line: &[String],
+) -> Result<String> {
+    Ok(format!(
+        "Error: settings.advanced.record_rules element \"{}\" is incorrect\nDescription: {description}\nElement: [{}]",
+        increment!(line_num),
+        line.iter().map(|x| format!("\"{x}\"")).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+pub fn make_evil_gmsts(list: Vec<Vec<String>>) -> Result<HashMap<String, GameSettingValue>> {
+    let mut res = HashMap::new();
+    for (n, line) in list.into_iter().enumerate() {
+        let [ref id, ref value_raw] = *line else {
+            let description = "Should contain 2 subelements [\"ID\", \"Value\"]";
+            return Err(anyhow!(make_evil_gmsts_err_text(description, n, &line)?));
+        };
+        let id_low = id.to_lowercase();
+        let value = match id_low.chars().next() {
+            Some('f') => value_raw.parse::<f32>().ok().map(GameSettingValue::Float),
+            Some('i') => value_raw.parse::<i32>().ok().map(GameSettingValue::Integer),
+            Some('s') => Some(GameSettingValue::String(value_raw.clone())),
+            _ => {
+                let description = "ID should start with \"f\", \"i\" or \"s\"";
+                return Err(anyhow!(make_evil_gmsts_err_text(description, n, &line)?));
+            }
+        };
+        let Some(value) = value else {
+            let description =
+                "Value should be a number of the type defined by the first letter of ID";
+            return Err(anyhow!(make_evil_gmsts_err_text(description, n, &line)?));
+        };
+        if res.insert(id_low, value).is_some() {
+            let description = "There is already a value for this ID";
+            return Err(anyhow!(make_evil_gmsts_err_text(description, n, &line)?));
+        }
+    }
+    Ok(res)
+}
+
+fn make_evil_gmsts_err_text(description: &str, line_num: usize, line: &[String]) -> Result<String> {
+    Ok(format!(
+        "Error: settings.advanced.evil_gmsts element \"{}\" is incorrect\nDescription: {description}\nElement: [{}]",
+        increment!(line_num),
+        line.iter().map(|x| format!("\"{x}\"")).collect::<Vec<_>>().join(", ")
+    ))
+}
+
 pub(in crate::config) fn prepare_plugin_extensions_to_ignore(list: &[String]) -> Vec<String> {
     let mut res = Vec::new();
     for extension in list {
@@ -416,3 +554,38 @@ pub(in crate::config) fn prepare_plugin_extensions_to_ignore(list: &[String]) ->
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::make_record_rules;
+
+    fn rule(record_type: &str, action: &str) -> Vec<Vec<String>> {
+        vec![vec![
+            record_type.to_owned(),
+            "test_id".to_owned(),
+            action.to_owned(),
+        ]]
+    }
+
+    #[test]
+    fn unsupported_types_are_rejected() {
+        for record_type in ["CELL", "LAND", "LTEX", "DIAL", "INFO", "SKIL", "MGEF"] {
+            assert!(
+                make_record_rules(rule(record_type, "keep_previous")).is_err(),
+                "rule for {record_type} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn all_actions_are_accepted_for_merged_types() {
+        for record_type in ["FACT", "PGRD", "LEVI", "NPC_"] {
+            for action in ["exclude", "keep_previous", "plugin:test.esp"] {
+                assert!(
+                    make_record_rules(rule(record_type, action)).is_ok(),
+                    "rule {action:?} for {record_type} should be accepted"
+                );
+            }
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use crate::{
-    msg, Cfg, Helper, Log, Mode, Out, StatsUpdateKind, CRC64, SNDG_ID_MAX_LEN, SNDG_ID_SUFFIX_LEN,
+    count_itm, get_provenance_cell_name, load_masters, msg, Cfg, Helper, Log, Mode, Out,
+    RecordRule, RecordRuleAction, StatsUpdateKind, CRC64, SNDG_ID_MAX_LEN, SNDG_ID_SUFFIX_LEN,
     SNDG_MAX_SOUND_FLAG,
 };
 use anyhow::{anyhow, Context as _, Result};
 use hashbrown::hash_map::Entry;
 use std::fmt::Write as _;
-use tes3::esp::{Plugin, SoundGen, StartScript, TES3Object};
+use tes3::esp::{EffectId, Plugin, SkillId, SoundGen, StartScript, TES3Object};
 mod cell;
 mod dial;
+mod fact;
 mod header;
 mod info;
+mod inventory;
 mod land;
 mod ltex;
+mod pgrd;
 
 #[allow(
     clippy::too_many_lines,
@@ -28,41 +32,95 @@ pub fn process_records(
 ) -> Result<()> {
     macro_rules! process {
         ($type:ident, $value:expr, $key:expr, $simple:expr) => {
-            match h.g.r.$type.entry($key) {
-                Entry::Vacant(v) => {
-                    let len = out.$type.len();
-                    out.$type.push(($value, Vec::new()));
-                    v.insert(len);
-                    h.l.stats.$type(StatsUpdateKind::Processed);
-                }
-                Entry::Occupied(o) => {
-                    let global_id = *o.get();
-                    let out_v = out.$type.get_mut(global_id).with_context(|| {
-                        format!(
-                            "Bug: indexing slicing out.{}[{global_id}]",
-                            stringify!($type)
-                        )
-                    })?;
-                    if out_v.0 != $value {
-                        if !$simple || h.g.list_options.debug {
-                            keep_previous!(out_v, $value);
-                        }
-                        out_v.0 = $value;
-                        h.l.stats.$type(StatsUpdateKind::Replaced);
-                    } else {
-                        if h.g.list_options.debug {
-                            keep_previous!(out_v, $value);
+            process!($type, $value, $key, $simple, None);
+        };
+        ($type:ident, $value:expr, $key:expr, $simple:expr, $merged:expr) => {
+            let key = $key;
+            let rule_outcome = get_record_rule_outcome(stringify!($type), &key, h, cfg);
+            // COMMENT: record with keep previous rule is never merged, otherwise merged version would replace kept versions, while version of the winning plugin is taken as is
+            let merged_last = if matches!(
+                rule_outcome,
+                RecordRuleOutcome::KeepPrevious | RecordRuleOutcome::Winner
+            ) {
+                None
+            } else {
+                $merged
+            };
+            if h.g.list_options.write_provenance {
+                h.g.provenance.add(
+                    stringify!($type),
+                    key.provenance_id(),
+                    h.l.plugin_info.id,
+                    !matches!(rule_outcome, RecordRuleOutcome::Ignore(_)),
+                );
+            }
+            if let RecordRuleOutcome::Ignore(reason) = rule_outcome {
+                h.g.record_rules_ignored.push(format!(
+                    "    Record {}: version of {:?} from plugin \"{}\" was ignored due to winning plugin rule\n      Reason: {reason}",
+                    stringify!($type).to_uppercase(),
+                    key,
+                    h.l.plugin_info.name
+                ));
+                h.l.stats.$type(StatsUpdateKind::Ignored);
+            } else {
+                match h.g.r.$type.entry(key) {
+                    Entry::Vacant(v) => {
+                        let len = out.$type.len();
+                        out.$type.push((merged_last.unwrap_or($value), Vec::new()));
+                        v.insert(len);
+                        h.l.stats.$type(StatsUpdateKind::Processed);
+                    }
+                    Entry::Occupied(o) => {
+                        let global_id = *o.get();
+                        let out_v = out.$type.get_mut(global_id).with_context(|| {
+                            format!(
+                                "Bug: indexing slicing out.{}[{global_id}]",
+                                stringify!($type)
+                            )
+                        })?;
+                        let is_changed = merged_last
+                            .as_ref()
+                            .map_or(out_v.0 != $value, |merged| out_v.0 != *merged);
+                        if is_changed {
+                            if !$simple
+                                || h.g.list_options.debug
+                                || matches!(rule_outcome, RecordRuleOutcome::KeepPrevious)
+                            {
+                                keep_previous!(out_v, $value);
+                            }
+                            out_v.0 = merged_last.unwrap_or($value);
+                            h.l.stats.$type(StatsUpdateKind::Replaced);
+                        } else {
+                            if h.g.list_options.debug {
+                                keep_previous!(out_v, $value);
+                            }
+                            h.l.stats.$type(StatsUpdateKind::Duplicate);
                         }
-                        h.l.stats.$type(StatsUpdateKind::Duplicate);
                     }
                 }
             }
         };
     }
+    macro_rules! provenance {
+        ($type:ident, $id:expr) => {
+            if h.g.list_options.write_provenance {
+                h.g.provenance
+                    .add(stringify!($type), $id, h.l.plugin_info.id, true);
+            }
+        };
+    }
     if h.g.list_options.insufficient_merge {
         for (count, record) in plugin.objects.into_iter().enumerate() {
+            if count > 0 && h.g.list_options.clean_itm {
+                count_itm(&record, h)?;
+            }
             match count {
-                0 => header::process(record, out, h)?,
+                0 => {
+                    header::process(record, out, h)?;
+                    if h.g.list_options.clean_itm {
+                        load_masters(out, h, cfg, log)?;
+                    }
+                }
                 _ => match record {
                     TES3Object::Static(v) => {
                         if matches!(h.g.list_options.mode, Mode::Grass)
@@ -71,7 +129,10 @@ pub fn process_records(
                             process!(stat, v, v.id.to_lowercase(), false);
                         }
                     }
-                    TES3Object::Cell(cell) => cell::process(cell, out, name, h, cfg, log)?,
+                    TES3Object::Cell(cell) => {
+                        provenance!(cell, get_provenance_cell_name(&cell).to_lowercase());
+                        cell::process(cell, out, name, h, cfg, log)?;
+                    }
                     TES3Object::Header(_) => {
                         return Err(anyhow!("Plugin is invalid due to many header records"))
                     }
@@ -82,11 +143,31 @@ pub fn process_records(
     } else {
         let mut land_found = false;
         for (count, record) in plugin.objects.into_iter().enumerate() {
+            if count > 0 && h.g.list_options.clean_itm {
+                count_itm(&record, h)?;
+            }
             match count {
-                0 => header::process(record, out, h)?,
+                0 => {
+                    header::process(record, out, h)?;
+                    if h.g.list_options.clean_itm
+                        || (h.g.list_options.merge_pathgrids
+                            && cfg.advanced.pathgrid_static_distance > 0)
+                    {
+                        load_masters(out, h, cfg, log)?;
+                    }
+                }
                 _ => match record {
-                    TES3Object::Dialogue(dial) => dial::process(dial, out, h, cfg, log)?,
-                    TES3Object::DialogueInfo(info) => info::process(info, out, h, cfg, log)?,
+                    TES3Object::Dialogue(dial) => {
+                        provenance!(dial, dial.id.to_lowercase());
+                        dial::process(dial, out, h, cfg, log)?;
+                    }
+                    TES3Object::DialogueInfo(info) => {
+                        provenance!(
+                            info,
+                            format!("{}|{}", h.l.active_dial_name_low, info.id.to_lowercase())
+                        );
+                        info::process(info, out, h, cfg, log)?;
+                    }
                     _ => {
                         if h.l.active_dial_id.is_some() {
                             h.l.active_dial_id = None;
@@ -99,7 +180,10 @@ pub fn process_records(
                                 process!(glob, v, v.id.to_lowercase(), true);
                             }
                             TES3Object::Class(v) => process!(clas, v, v.id.to_lowercase(), false),
-                            TES3Object::Faction(v) => process!(fact, v, v.id.to_lowercase(), true),
+                            TES3Object::Faction(v) => {
+                                let merged = fact::process(&v, out, h)?;
+                                process!(fact, v, v.id.to_lowercase(), false, merged);
+                            }
                             TES3Object::Race(v) => process!(race, v, v.id.to_lowercase(), false),
                             TES3Object::Sound(v) => process!(soun, v, v.id.to_lowercase(), false),
                             TES3Object::SoundGen(mut v) => {
@@ -118,6 +202,7 @@ pub fn process_records(
                                 process!(sscr, v, v.id.to_lowercase(), true);
                             }
                             TES3Object::LandscapeTexture(ltex) => {
+                                provenance!(ltex, ltex.id.to_lowercase());
                                 ltex::process(ltex, land_found, out, h)?;
                             }
                             TES3Object::Spell(v) => process!(spel, v, v.id.to_lowercase(), false),
@@ -128,10 +213,20 @@ pub fn process_records(
                             }
                             TES3Object::Weapon(v) => process!(weap, v, v.id.to_lowercase(), false),
                             TES3Object::Container(v) => {
-                                process!(cont, v, v.id.to_lowercase(), false);
+                                let merged = if h.g.list_options.merge_inventories {
+                                    inventory::process_cont(&v, out, h)?
+                                } else {
+                                    None
+                                };
+                                process!(cont, v, v.id.to_lowercase(), false, merged);
                             }
                             TES3Object::Creature(v) => {
-                                process!(crea, v, v.id.to_lowercase(), false);
+                                let merged = if h.g.list_options.merge_inventories {
+                                    inventory::process_crea(&v, out, h)?
+                                } else {
+                                    None
+                                };
+                                process!(crea, v, v.id.to_lowercase(), false, merged);
                             }
                             TES3Object::Bodypart(v) => {
                                 process!(body, v, v.id.to_lowercase(), false);
@@ -140,7 +235,14 @@ pub fn process_records(
                             TES3Object::Enchanting(v) => {
                                 process!(ench, v, v.id.to_lowercase(), false);
                             }
-                            TES3Object::Npc(v) => process!(npc_, v, v.id.to_lowercase(), false),
+                            TES3Object::Npc(v) => {
+                                let merged = if h.g.list_options.merge_inventories {
+                                    inventory::process_npc_(&v, out, h)?
+                                } else {
+                                    None
+                                };
+                                process!(npc_, v, v.id.to_lowercase(), false, merged);
+                            }
                             TES3Object::Armor(v) => process!(armo, v, v.id.to_lowercase(), false),
                             TES3Object::Clothing(v) => {
                                 process!(clot, v, v.id.to_lowercase(), false);
@@ -169,12 +271,24 @@ pub fn process_records(
                             TES3Object::LeveledCreature(v) => {
                                 process!(levc, v, v.id.to_lowercase(), false);
                             }
-                            TES3Object::Cell(cell) => cell::process(cell, out, name, h, cfg, log)?,
+                            TES3Object::Cell(cell) => {
+                                provenance!(cell, get_provenance_cell_name(&cell).to_lowercase());
+                                cell::process(cell, out, name, h, cfg, log)?;
+                            }
                             TES3Object::Landscape(land) => {
+                                provenance!(land, format!("{:?}", land.grid));
                                 land::process(land, &mut land_found, out, h)?;
                             }
                             TES3Object::PathGrid(v) => {
-                                process!(pgrd, v, v.cell.to_lowercase(), true);
+                                // COMMENT: pathgrid with keep previous or winner rule isn't merged, so that the rule applies to it as to other records
+                                if h.g.list_options.merge_pathgrids
+                                    && !has_version_rule("pgrd", &v.cell.to_lowercase(), cfg)
+                                {
+                                    provenance!(pgrd, v.cell.to_lowercase());
+                                    pgrd::process(v, out, h, cfg)?;
+                                } else {
+                                    process!(pgrd, v, v.cell.to_lowercase(), true);
+                                }
                             }
                             TES3Object::Header(_) => {
                                 return Err(anyhow!("Plugin is invalid due to many header records"))
@@ -200,6 +314,113 @@ macro_rules! keep_previous {
 
 pub(in crate::input) use keep_previous;
 
+macro_rules! get_base_and_last {
+    ($type:ident, $value:ident, $out:ident, $h:ident) => {
+        match $h.g.r.$type.get(&$value.id.to_lowercase()) {
+            None => return Ok(None),
+            Some(&global_id) => {
+                let &(ref last, ref prevs) = $out.$type.get(global_id).with_context(|| {
+                    format!(
+                        "Bug: indexing slicing out.{}[{global_id}]",
+                        stringify!($type)
+                    )
+                })?;
+                (prevs.first().unwrap_or(last), last)
+            }
+        }
+    };
+}
+
+pub(in crate::input) use get_base_and_last;
+
+enum RecordRuleOutcome<'a> {
+    Default,
+    KeepPrevious,
+    Winner,
+    Ignore(&'a str),
+}
+
+trait RecordRuleKey {
+    fn rule_id(&self) -> Option<&str>;
+    fn provenance_id(&self) -> String;
+}
+
+impl RecordRuleKey for String {
+    fn rule_id(&self) -> Option<&str> {
+        Some(self)
+    }
+
+    fn provenance_id(&self) -> String {
+        self.clone()
+    }
+}
+
+impl RecordRuleKey for SkillId {
+    fn rule_id(&self) -> Option<&str> {
+        None
+    }
+
+    fn provenance_id(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+impl RecordRuleKey for EffectId {
+    fn rule_id(&self) -> Option<&str> {
+        None
+    }
+
+    fn provenance_id(&self) -> String {
+        format!("{self:?}")
+    }
+}
+
+fn has_version_rule(record_type: &str, id_low: &str, cfg: &Cfg) -> bool {
+    !cfg.advanced.record_rules.is_empty()
+        && matches!(
+            cfg.advanced.record_rules.get(record_type, id_low),
+            Some(&RecordRule {
+                action: RecordRuleAction::KeepPrevious | RecordRuleAction::Winner(_),
+                ..
+            })
+        )
+}
+
+fn get_record_rule_outcome<'a, K: RecordRuleKey>(
+    record_type: &'static str,
+    key: &K,
+    h: &mut Helper,
+    cfg: &'a Cfg,
+) -> RecordRuleOutcome<'a> {
+    if cfg.advanced.record_rules.is_empty() {
+        return RecordRuleOutcome::Default;
+    }
+    let Some(id_low) = key.rule_id() else {
+        return RecordRuleOutcome::Default;
+    };
+    let Some(rule) = cfg.advanced.record_rules.get(record_type, id_low) else {
+        return RecordRuleOutcome::Default;
+    };
+    match rule.action {
+        RecordRuleAction::Exclude => RecordRuleOutcome::Default,
+        RecordRuleAction::KeepPrevious => RecordRuleOutcome::KeepPrevious,
+        RecordRuleAction::Winner(ref plugin_name_low) => {
+            let winner_key = (record_type, id_low.to_owned());
+            h.g.r
+                .record_rule_winner_plugins
+                .insert(winner_key.clone(), plugin_name_low.clone());
+            if *plugin_name_low == h.l.plugin_info.name_low {
+                h.g.r.record_rule_winners.insert(winner_key);
+                RecordRuleOutcome::Winner
+            } else if h.g.r.record_rule_winners.contains(&winner_key) {
+                RecordRuleOutcome::Ignore(&rule.reason)
+            } else {
+                RecordRuleOutcome::Default
+            }
+        }
+    }
+}
+
 pub fn assign_id_to_sscr_with_empty_id(
     sscr: &mut StartScript,
     cfg: &Cfg,
@@ -1,8 +1,8 @@
 use crate::{
-    increment, msg, references_sorted, CellExtGrid, CellMeta, Cfg, Helper, IgnoredRefError,
-    ListOptions, LocalMaster, LocalMergedMaster, Log, MastId, MergedPluginMeta, MergedPluginRefr,
-    Mode, MovedInstanceGrids, MovedInstanceId, OldRefSources, Out, RefSources, RefrId,
-    StatsUpdateKind,
+    increment, is_grass_cell_excluded, is_grass_kept, msg, references_sorted, CellExtGrid,
+    CellMeta, Cfg, Helper, IgnoredRefError, ListOptions, LocalMaster, LocalMergedMaster, Log,
+    MastId, MergedPluginMeta, MergedPluginRefr, Mode, MovedInstanceGrids, MovedInstanceId,
+    OldRefSources, Out, RefSources, RefrId, StatsUpdateKind,
 };
 use anyhow::{anyhow, Context as _, Result};
 use hashbrown::{hash_map::Entry, HashMap};
@@ -29,11 +29,16 @@ pub fn process(
     let mut plugin_refrs: Vec<MergedPluginRefr> = Vec::new();
     #[allow(clippy::wildcard_enum_match_arm)]
     let mut local_references: Vec<&Reference> = match h.g.list_options.mode {
-        Mode::Grass => cell
-            .references
-            .values()
-            .filter(|x| !cfg.advanced.grass_filter.contains(&x.id.to_lowercase()))
-            .collect(),
+        Mode::Grass => {
+            if is_grass_cell_excluded(&cell, &h.g.list_options) {
+                Vec::new()
+            } else {
+                cell.references
+                    .values()
+                    .filter(|x| is_grass_kept(x, cell.data.grid, out, h, cfg))
+                    .collect()
+            }
+        }
 
         _ => cell.references.values().collect(),
     };
@@ -206,14 +211,22 @@ pub fn process(
                             match h.l.merged_masters.iter().find(|x| x.local_id == local_reference.mast_index) {
                                 Some(local_merged_master) => {
                                     match get_global_refr(local_reference, local_merged_master, &o.get().plugin_metas) {
-                                        Ok(refr_index) => modify_global_reference(
-                                            local_reference,
-                                            &mut o_cell.0.references,
-                                            refr_index,
-                                            &mut h.g.r.moved_instances,
-                                            $grid,
-                                        )
-                                        .with_context(|| format!("Failed to modify global reference"))?,
+                                        Ok(refr_index) => {
+                                            modify_global_reference(
+                                                local_reference,
+                                                &mut o_cell.0.references,
+                                                refr_index,
+                                                &mut h.g.r.moved_instances,
+                                                $grid,
+                                            )
+                                            .with_context(|| format!("Failed to modify global reference"))?;
+                                            if h.g.list_options.write_provenance {
+                                                h.g.provenance.ref_editors.insert(
+                                                    (local_merged_master.name_low.clone(), local_reference.refr_index),
+                                                    (h.l.plugin_info.name_low.clone(), local_reference.mast_index),
+                                                );
+                                            }
+                                        }
                                         Err(err) => {
                                             missing_ref_text(
                                                 &mut h.t.missing_ref_text,
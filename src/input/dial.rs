@@ -1,4 +1,4 @@
-use crate::{msg, Cfg, Dial, DialMeta, Helper, Log, Out, StatsUpdateKind};
+use crate::{msg, Cfg, Dial, DialMeta, Helper, Log, Out, StatsUpdateKind, WarningKind};
 use anyhow::{Context as _, Result};
 use hashbrown::{hash_map::Entry, HashMap};
 use tes3::esp::Dialogue;
@@ -45,6 +45,7 @@ pub fn process(
                         out_dialogue.dialogue_type,
                         dial.dialogue_type
                     );
+                    log.collect_warning(WarningKind::DialogueTypeChange, &text);
                     msg(text, 0, cfg, log)?;
                 }
                 *out_dialogue = dial;
@@ -0,0 +1,173 @@
+use super::get_base_and_last;
+use crate::{Helper, Out};
+use anyhow::{Context as _, Result};
+use tes3::esp::Faction;
+
+// COMMENT: merged record becomes the last version, while the plugin's own version is kept in history
+pub fn process(fact: &Faction, out: &Out, h: &Helper) -> Result<Option<Faction>> {
+    let (base, last) = get_base_and_last!(fact, fact, out, h);
+    Ok(Some(merge(base, last, fact)))
+}
+
+// COMMENT: reactions are united by faction id, each reaction and rank is taken from the last version that changed it compared to the base
+fn merge(base: &Faction, last: &Faction, fact: &Faction) -> Faction {
+    let mut merged_fact = fact.clone();
+    for merged in &mut merged_fact.reactions {
+        let is_base = base.reactions.iter().any(|reaction| {
+            reaction.faction.eq_ignore_ascii_case(&merged.faction)
+                && reaction.reaction == merged.reaction
+        });
+        if is_base {
+            if let Some(reaction) = last
+                .reactions
+                .iter()
+                .find(|reaction| reaction.faction.eq_ignore_ascii_case(&merged.faction))
+            {
+                merged.clone_from(reaction);
+            }
+        }
+    }
+    for reaction in &last.reactions {
+        if !merged_fact
+            .reactions
+            .iter()
+            .any(|merged| merged.faction.eq_ignore_ascii_case(&reaction.faction))
+        {
+            merged_fact.reactions.push(reaction.clone());
+        }
+    }
+    let ranks_len = fact.rank_names.len().max(last.rank_names.len());
+    merged_fact.rank_names.clear();
+    for index in 0..ranks_len {
+        let is_changed = index < fact.rank_names.len() && is_rank_changed(fact, base, index);
+        let source = if is_changed || index >= last.rank_names.len() {
+            fact
+        } else {
+            last
+        };
+        if let (Some(rank_name), Some(requirement), Some(merged_requirement)) = (
+            source.rank_names.get(index),
+            source.data.requirements.get(index),
+            merged_fact.data.requirements.get_mut(index),
+        ) {
+            merged_fact.rank_names.push(rank_name.clone());
+            merged_requirement.clone_from(requirement);
+        }
+    }
+    merged_fact
+}
+
+fn is_rank_changed(fact: &Faction, base: &Faction, index: usize) -> bool {
+    fact.rank_names.get(index) != base.rank_names.get(index)
+        || fact.data.requirements.get(index) != base.data.requirements.get(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge;
+    use tes3::esp::{Faction, FactionReaction};
+
+    fn get_faction(reactions: &[(&str, i32)], rank_names: &[&str]) -> Faction {
+        let mut fact = Faction {
+            id: "test_faction".to_owned(),
+            reactions: reactions
+                .iter()
+                .map(|&(faction, reaction)| FactionReaction {
+                    faction: faction.to_owned(),
+                    reaction,
+                })
+                .collect(),
+            rank_names: rank_names.iter().map(|&rank| rank.to_owned()).collect(),
+            ..Default::default()
+        };
+        for (requirement, reputation) in fact.data.requirements.iter_mut().zip(1..) {
+            requirement.reputation = reputation;
+        }
+        fact
+    }
+
+    #[test]
+    fn reactions_are_united_and_last_value_wins() {
+        let last = get_faction(&[("a", 1), ("b", 2)], &[]);
+        let fact = get_faction(&[("A", -1), ("c", 3)], &[]);
+        let merged = merge(&last, &last, &fact);
+        let reactions = merged
+            .reactions
+            .iter()
+            .map(|reaction| (reaction.faction.as_str(), reaction.reaction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reactions,
+            [("A", -1), ("c", 3), ("b", 2)],
+            "reactions should be united by faction id with the plugin's value winning"
+        );
+    }
+
+    #[test]
+    fn missing_ranks_are_taken_from_last() {
+        let last = get_faction(&[], &["Novice", "Adept", "Master"]);
+        let mut fact = get_faction(&[], &["Beginner"]);
+        if let Some(requirement) = fact.data.requirements.get_mut(1) {
+            requirement.reputation = 100;
+        }
+        let merged = merge(&last, &last, &fact);
+        assert_eq!(
+            merged.rank_names,
+            ["Beginner", "Adept", "Master"],
+            "ranks missing from the plugin should be taken from the previous version"
+        );
+        assert_eq!(
+            merged
+                .data
+                .requirements
+                .iter()
+                .take(3)
+                .map(|requirement| requirement.reputation)
+                .collect::<Vec<_>>(),
+            [1, 2, 3],
+            "requirements of the taken ranks should be taken too"
+        );
+    }
+
+    #[test]
+    fn ranks_of_plugin_are_kept() {
+        let last = get_faction(&[], &["Novice"]);
+        let fact = get_faction(&[], &["Beginner", "Adept"]);
+        assert_eq!(
+            merge(&last, &last, &fact).rank_names,
+            ["Beginner", "Adept"],
+            "ranks of the plugin should win"
+        );
+    }
+
+    #[test]
+    fn ranks_are_merged_against_base() {
+        let base = get_faction(&[], &["Novice", "Adept"]);
+        let version_1 = get_faction(&[], &["Beginner", "Adept"]);
+        let version_2 = get_faction(&[], &["Novice", "Expert"]);
+        let last = merge(&base, &base, &version_1);
+        assert_eq!(
+            merge(&base, &last, &version_2).rank_names,
+            ["Beginner", "Expert"],
+            "rank unchanged by the plugin shouldn't revert the earlier change"
+        );
+    }
+
+    #[test]
+    fn reactions_are_merged_against_base() {
+        let base = get_faction(&[("a", 1), ("b", 2)], &[]);
+        let version_1 = get_faction(&[("a", 5), ("b", 2)], &[]);
+        let version_2 = get_faction(&[("a", 1), ("b", -2)], &[]);
+        let last = merge(&base, &base, &version_1);
+        let reactions = merge(&base, &last, &version_2)
+            .reactions
+            .iter()
+            .map(|reaction| (reaction.faction.clone(), reaction.reaction))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            reactions,
+            [("a".to_owned(), 5), ("b".to_owned(), -2)],
+            "reaction unchanged by the plugin shouldn't revert the earlier change"
+        );
+    }
+}
@@ -9,6 +9,11 @@ pub fn process(record: TES3Object, out: &mut Out, h: &mut Helper) -> Result<()>
     let TES3Object::Header(header) = record else {
         return Err(anyhow!("Plugin's first record is not a header"));
     };
+    if h.g.list_options.write_provenance {
+        h.g.provenance
+            .masters
+            .insert(h.l.plugin_info.id, header.masters.clone());
+    }
     for (&(ref master_name, master_size), id) in header.masters.iter().zip(1_u32..) {
         let name_low: MasterNameLow = master_name.to_lowercase();
         match h
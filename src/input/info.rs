@@ -63,6 +63,7 @@ pub fn process(
         },
     };
     out_dial.info.push(info);
+    out_dial.info_plugin_ids.push(h.l.plugin_info.id);
     Ok(())
 }
 
@@ -0,0 +1,130 @@
+use super::get_base_and_last;
+use crate::{Helper, Out};
+use anyhow::{Context as _, Result};
+use tes3::esp::{Container, Creature, FixedString, Npc};
+
+// COMMENT: merged record becomes the last version, while the plugin's own version is kept in history
+pub fn process_cont(cont: &Container, out: &Out, h: &Helper) -> Result<Option<Container>> {
+    let (base, last) = get_base_and_last!(cont, cont, out, h);
+    let mut merged = cont.clone();
+    merged.inventory = merge_list(&base.inventory, &last.inventory, &cont.inventory, same_item);
+    Ok(Some(merged))
+}
+
+macro_rules! process_actor {
+    ($func:ident, $type:ident, $record:ty) => {
+        pub fn $func(actor: &$record, out: &Out, h: &Helper) -> Result<Option<$record>> {
+            let (base, last) = get_base_and_last!($type, actor, out, h);
+            let mut merged = actor.clone();
+            merged.inventory = merge_list(
+                &base.inventory,
+                &last.inventory,
+                &actor.inventory,
+                same_item,
+            );
+            merged.spells = merge_list(&base.spells, &last.spells, &actor.spells, |a, b| {
+                a.eq_ignore_ascii_case(b)
+            });
+            merged.travel_destinations = merge_list(
+                &base.travel_destinations,
+                &last.travel_destinations,
+                &actor.travel_destinations,
+                PartialEq::eq,
+            );
+            Ok(Some(merged))
+        }
+    };
+}
+
+process_actor!(process_npc_, npc_, Npc);
+process_actor!(process_crea, crea, Creature);
+
+fn merge_list<T: Clone + PartialEq>(
+    base: &[T],
+    last: &[T],
+    version: &[T],
+    same: fn(&T, &T) -> bool,
+) -> Vec<T> {
+    let mut merged: Vec<T> = last
+        .iter()
+        .filter(|entry| {
+            !base.iter().any(|b| same(b, entry)) || version.iter().any(|v| same(v, entry))
+        })
+        .cloned()
+        .collect();
+    for entry in version {
+        if base.contains(entry) {
+            continue;
+        }
+        match merged.iter_mut().find(|m| same(m, entry)) {
+            Some(m) => m.clone_from(entry),
+            None => merged.push(entry.clone()),
+        }
+    }
+    merged
+}
+
+fn same_item(a: &(i32, FixedString<32>), b: &(i32, FixedString<32>)) -> bool {
+    a.1 .0.eq_ignore_ascii_case(&b.1 .0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_list, same_item};
+    use tes3::esp::FixedString;
+
+    type Item = (i32, FixedString<32>);
+
+    fn items(list: &[(i32, &str)]) -> Vec<Item> {
+        list.iter()
+            .map(|&(count, id)| (count, FixedString(id.to_owned())))
+            .collect()
+    }
+
+    #[test]
+    fn additions_of_last_and_version_are_kept() {
+        let base = items(&[(1, "a")]);
+        let last = items(&[(1, "a"), (2, "b")]);
+        let version = items(&[(1, "a"), (3, "c")]);
+        assert_eq!(
+            merge_list(&base, &last, &version, same_item),
+            items(&[(1, "a"), (2, "b"), (3, "c")]),
+            "additions of both plugins should be kept"
+        );
+    }
+
+    #[test]
+    fn removals_of_last_and_version_are_kept() {
+        let base = items(&[(1, "a"), (1, "b")]);
+        let last = items(&[(1, "b")]);
+        let version = items(&[(1, "a")]);
+        assert!(
+            merge_list(&base, &last, &version, same_item).is_empty(),
+            "items removed by any plugin should stay removed"
+        );
+    }
+
+    #[test]
+    fn changed_count_replaces_the_item() {
+        let base = items(&[(1, "a")]);
+        let last = items(&[(1, "a")]);
+        let version = items(&[(-5, "A")]);
+        assert_eq!(
+            merge_list(&base, &last, &version, same_item),
+            items(&[(-5, "A")]),
+            "item with changed count should replace the previous one"
+        );
+    }
+
+    #[test]
+    fn spells_are_compared_by_caller_rule() {
+        let base = vec!["Spell".to_owned()];
+        let last = vec!["spell".to_owned(), "other".to_owned()];
+        let version = vec!["SPELL".to_owned()];
+        assert_eq!(
+            merge_list(&base, &last, &version, |a, b| a.eq_ignore_ascii_case(b)),
+            ["SPELL", "other"],
+            "spell in another case should be the same spell"
+        );
+    }
+}
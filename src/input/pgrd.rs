@@ -0,0 +1,235 @@
+use super::keep_previous;
+use crate::{Cfg, Helper, Out, StatsUpdateKind};
+use anyhow::{anyhow, Context as _, Result};
+use hashbrown::hash_map::Entry;
+use std::collections::BTreeSet;
+use tes3::esp::{PathGrid, PathGridPoint};
+
+pub fn process(pgrd: PathGrid, out: &mut Out, h: &mut Helper, cfg: &Cfg) -> Result<()> {
+    match h.g.r.pgrd.entry(pgrd.cell.to_lowercase()) {
+        Entry::Vacant(v) => {
+            let pgrd_len = out.pgrd.len();
+            out.pgrd.push((pgrd, Vec::new()));
+            v.insert(pgrd_len);
+            h.l.stats.pgrd(StatsUpdateKind::Processed);
+        }
+        Entry::Occupied(o) => {
+            let pgrd_global_id = *o.get();
+            let out_v = out.pgrd.get_mut(pgrd_global_id).with_context(|| {
+                format!("Bug: out.pgrd doesn't contain index = \"{pgrd_global_id}\"")
+            })?;
+            // COMMENT: exterior pathgrids may share the same name(e.g. region name), so they are only merged for the same grid
+            let is_mergeable = out_v.0.data.grid == pgrd.data.grid;
+            let new_pgrd = if is_mergeable {
+                merge(&out_v.0, &pgrd, cfg.advanced.pathgrid_point_tolerance)
+                    .with_context(|| format!("Failed to merge PGRD \"{}\"", pgrd.cell))?
+            } else {
+                pgrd.clone()
+            };
+            if h.g.list_options.debug {
+                keep_previous!(out_v, pgrd);
+            }
+            if out_v.0 == new_pgrd {
+                h.l.stats.pgrd(StatsUpdateKind::Duplicate);
+            } else {
+                out_v.0 = new_pgrd;
+                h.l.stats.pgrd(if is_mergeable {
+                    StatsUpdateKind::Merged
+                } else {
+                    StatsUpdateKind::Replaced
+                });
+            }
+        }
+    };
+    Ok(())
+}
+
+fn merge(last: &PathGrid, pgrd: &PathGrid, tolerance: i32) -> Result<PathGrid> {
+    let mut points: Vec<PathGridPoint> = last.points.clone();
+    let mut edges: BTreeSet<(usize, usize)> = get_edges(last)?.into_iter().collect();
+    let mut remap: Vec<usize> = Vec::with_capacity(pgrd.points.len());
+    for point in &pgrd.points {
+        // COMMENT: points are only matched against the previous version, so that close points of the same plugin are kept
+        match last
+            .points
+            .iter()
+            .position(|existing| is_same_point(existing, point, tolerance))
+        {
+            Some(index) => remap.push(index),
+            None => {
+                remap.push(points.len());
+                points.push(*point);
+            }
+        }
+    }
+    for (from, to) in get_edges(pgrd)? {
+        let (Some(&new_from), Some(&new_to)) = (remap.get(from), remap.get(to)) else {
+            return Err(anyhow!(
+                "Bug: failed to remap pathgrid connection \"{from}\" -> \"{to}\""
+            ));
+        };
+        if new_from != new_to {
+            edges.insert((new_from, new_to));
+        }
+    }
+    let mut connections: Vec<u32> = Vec::with_capacity(edges.len());
+    for (index, point) in points.iter_mut().enumerate() {
+        let mut connection_count: u8 = 0;
+        for &(_, to) in edges.range((index, 0)..(index.saturating_add(1), 0)) {
+            connections.push(u32::try_from(to).with_context(|| {
+                format!("Bug: failed to cast {to:?}(pathgrid point index, usize) to u32")
+            })?);
+            connection_count = connection_count.checked_add(1).with_context(|| {
+                format!("Pathgrid point \"{index}\" has too many connections to merge")
+            })?;
+        }
+        point.connection_count = connection_count;
+    }
+    let mut merged_pgrd = pgrd.clone();
+    merged_pgrd.data.point_count = u16::try_from(points.len())
+        .with_context(|| format!("Pathgrid has too many points({}) to merge", points.len()))?;
+    merged_pgrd.points = points;
+    merged_pgrd.connections = connections;
+    Ok(merged_pgrd)
+}
+
+fn get_edges(pgrd: &PathGrid) -> Result<Vec<(usize, usize)>> {
+    let mut edges = Vec::with_capacity(pgrd.connections.len());
+    let mut connections = pgrd.connections.iter();
+    for (from, point) in pgrd.points.iter().enumerate() {
+        for _ in 0..point.connection_count {
+            let to = connections.next().with_context(|| {
+                format!(
+                    "Pathgrid \"{}\" is corrupted: connection count exceeds connections list",
+                    pgrd.cell
+                )
+            })?;
+            edges.push((
+                from,
+                usize::try_from(*to).with_context(|| {
+                    format!("Bug: failed to cast {to:?}(pathgrid connection, u32) to usize")
+                })?,
+            ));
+        }
+    }
+    Ok(edges)
+}
+
+fn is_same_point(first: &PathGridPoint, second: &PathGridPoint, tolerance: i32) -> bool {
+    first
+        .location
+        .iter()
+        .zip(second.location.iter())
+        .all(|(a, b)| a.abs_diff(*b) <= tolerance.unsigned_abs())
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::merge;
+    use tes3::esp::{PathGrid, PathGridPoint};
+
+    const TOLERANCE: i32 = 10;
+
+    fn get_pgrd(locations: &[[i32; 3]], edges: &[(usize, &[u32])]) -> PathGrid {
+        let points = locations
+            .iter()
+            .enumerate()
+            .map(|(index, &location)| PathGridPoint {
+                location,
+                connection_count: edges
+                    .iter()
+                    .find(|&&(from, _)| from == index)
+                    .map_or(0, |&(_, to)| {
+                        u8::try_from(to.len()).expect("too many connections")
+                    }),
+                ..Default::default()
+            })
+            .collect();
+        let mut connections = Vec::new();
+        for index in 0..locations.len() {
+            if let Some(&(_, to)) = edges.iter().find(|&&(from, _)| from == index) {
+                connections.extend_from_slice(to);
+            }
+        }
+        PathGrid {
+            cell: "Test Cell".to_owned(),
+            points,
+            connections,
+            ..Default::default()
+        }
+    }
+
+    fn get_locations(pgrd: &PathGrid) -> Vec<[i32; 3]> {
+        pgrd.points.iter().map(|point| point.location).collect()
+    }
+
+    #[test]
+    fn points_and_connections_are_united() {
+        let last = get_pgrd(&[[0, 0, 0], [100, 0, 0]], &[(0, &[1]), (1, &[0])]);
+        let pgrd = get_pgrd(&[[5, 0, 0], [0, 100, 0]], &[(0, &[1]), (1, &[0])]);
+        let merged = merge(&last, &pgrd, TOLERANCE).expect("failed to merge");
+        assert_eq!(
+            get_locations(&merged),
+            [[0, 0, 0], [100, 0, 0], [0, 100, 0]],
+            "point within tolerance should be matched, other points should be appended"
+        );
+        assert_eq!(
+            merged
+                .points
+                .iter()
+                .map(|point| point.connection_count)
+                .collect::<Vec<_>>(),
+            [2, 1, 1],
+            "connection counts should match united connections"
+        );
+        assert_eq!(
+            merged.connections,
+            [1, 2, 0, 0],
+            "connections should be remapped to merged points"
+        );
+        assert_eq!(merged.data.point_count, 3, "point count wasn't updated");
+    }
+
+    #[test]
+    fn close_points_of_the_same_plugin_are_kept() {
+        let last = get_pgrd(&[], &[]);
+        let pgrd = get_pgrd(&[[0, 0, 0], [3, 0, 0]], &[(0, &[1])]);
+        let merged = merge(&last, &pgrd, TOLERANCE).expect("failed to merge");
+        assert_eq!(
+            get_locations(&merged),
+            [[0, 0, 0], [3, 0, 0]],
+            "close points of the plugin shouldn't be matched against each other"
+        );
+        assert_eq!(merged.connections, [1], "connection was lost");
+    }
+
+    #[test]
+    fn connection_between_matched_points_is_dropped() {
+        let last = get_pgrd(&[[0, 0, 0]], &[]);
+        let pgrd = get_pgrd(&[[1, 0, 0], [2, 0, 0]], &[(0, &[1])]);
+        let merged = merge(&last, &pgrd, TOLERANCE).expect("failed to merge");
+        assert_eq!(
+            get_locations(&merged),
+            [[0, 0, 0]],
+            "both points should be matched to the existing one"
+        );
+        assert!(
+            merged.connections.is_empty(),
+            "connection of the point to itself should be dropped"
+        );
+    }
+
+    #[test]
+    fn corrupted_connections_are_an_error() {
+        let last = get_pgrd(&[[0, 0, 0]], &[]);
+        let mut pgrd = get_pgrd(&[[100, 0, 0]], &[]);
+        if let Some(point) = pgrd.points.first_mut() {
+            point.connection_count = 1;
+        }
+        assert!(
+            merge(&last, &pgrd, TOLERANCE).is_err(),
+            "connection count exceeding connections list should be an error"
+        );
+    }
+}
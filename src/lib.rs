@@ -0,0 +1,390 @@
+/*
+ *  Habasi - TES3 plugin merging and utility tool
+ *
+ *  Copyright (C) 2023 alvazir
+ *
+ *  This program is free software: you can redistribute it and/or modify
+ *  it under the terms of the GNU General Public License as published by
+ *  the Free Software Foundation, either version 3 of the License, or
+ *  (at your option) any later version.
+ *
+ *  This program is distributed in the hope that it will be useful,
+ *  but WITHOUT ANY WARRANTY; without even the implied warranty of
+ *  MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ *  GNU General Public License for more details.
+ *
+ *  You should have received a copy of the GNU General Public License
+ *  along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Habasi - TES3 plugin merging and utility tool.
+//!
+//! Command line program is a thin wrapper over [`run`]. Merging may also be embedded with [`MergeJob`]:
+//!
+//! ```no_run
+//! use habasi::{Cfg, MergeJob};
+//!
+//! let cfg = Cfg::from_args(["habasi", "--no-log", "--quiet"])?;
+//! let list_options = cfg.parse_list_options(&["replace", "dry_run"])?;
+//! let plugins = vec!["Plugin1.esp".to_owned(), "Plugin2.esp".to_owned()];
+//! let result = MergeJob::new("MergedPlugin.esp", plugins, list_options).run(&cfg)?;
+//! println!("{}", result.stats);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context as _, Result};
+use std::{
+    io::{Error as IOError, ErrorKind},
+    time::Instant,
+};
+use tes3::esp::Plugin;
+mod api;
+mod assets;
+mod config;
+mod input;
+mod load_order;
+mod output;
+mod stats;
+mod structs;
+mod util;
+pub use api::{MergeJob, MergeResult};
+use assets::{bsa::Bsa, make_tng_meshes::make_tng_meshes};
+pub use config::Cfg;
+use config::{RecordRule, RecordRuleAction};
+use output::{
+    make_output_plugin::make_output_plugin,
+    make_turn_normal_grass::make_turn_normal_grass,
+    manifest::{get_manifest, is_list_unchanged, write_manifest},
+    provenance::write_provenance,
+    ref_map::{seed_ref_map, write_ref_map},
+    transform::transform,
+    unmerge::unmerge,
+    write_output_plugin::write_output_plugin,
+};
+pub use stats::Stats;
+use stats::StatsUpdateKind;
+use structs::{
+    dial::{Dial, DialMeta},
+    diff::{DiffKind, PluginDiff, RecordDiff, RefDiff},
+    helper::Helper,
+    list_options::IndirectListOptions,
+    manifest::{ManifestFile, ManifestInput},
+    mode::Mode,
+    out::Out,
+    parsed_plugins::ParsedPlugins,
+    plugin_cache::PluginCacheKey,
+    provenance::{
+        ProvenanceCell, ProvenanceFile, ProvenancePlugin, ProvenanceRecord, ProvenanceRef,
+    },
+    ref_map::{RefMapFile, RefMapKey, RefMapPlugin},
+    turn_normal_grass::TurnNormalGrass,
+    Assets, CellExtGrid, CellMeta, FallbackStatics, FileInBsa, GlobalMaster, GlobalVtexId,
+    GrassGridRect, HeaderText, IgnoredRefError, LoadOrder, LocalMaster, LocalMergedMaster,
+    LocalVtexId, MastId, MasterNameLow, MergedPluginMeta, MergedPluginRefr, MovedInstanceGrids,
+    MovedInstanceId, OldRefSources, PluginInfo, PluginName, RefSources, RefrId, RegexPluginInfo,
+};
+pub use structs::{
+    list_options::ListOptions,
+    warning::{Warning, WarningKind},
+};
+pub use tes3;
+#[cfg(test)]
+use util::get_test_cfg_and_log;
+use util::{
+    create_dir_early,
+    diff::{diff_plugins, is_cell_properties_equal, show_plugins_diff},
+    get_base_dir_path, get_cell_name, get_game_config_string, get_provenance_cell_name,
+    get_record_key,
+    grass::{
+        check_grass_density, get_grass_density, get_grass_exclude_grids, get_grass_exclude_meshes,
+        get_grass_exclude_regions, is_grass_cell_excluded, is_grass_kept,
+        parse_grass_exclude_grids, parse_grass_exclude_meshes, parse_grass_exclude_regions,
+        show_grass_list_options,
+    },
+    header::{select_header_description, truncate_header_text},
+    increment,
+    itm::{clean_itm, count_itm, load_masters},
+    load_order::{
+        get_append_to_use_load_order_string, get_expanded_plugin_list,
+        get_skip_from_use_load_order_string, get_skip_plugin_name_low,
+    },
+    log::{show_log_path, Log},
+    msg::{err_or_ignore, err_or_ignore_thread_safe, msg, msg_no_log},
+    patterns::get_regex_plugin_list,
+    plugin_cache::{load_plugin, prepare_plugin_cache},
+    presets::check_presets,
+    process_plugin, read_lines,
+    references::{
+        nan_to_zero, process_moved_instances, references_sorted, show_ignored_ref_errors,
+    },
+    should_skip_list, show_global_list_options, show_record_notes, show_removed_record_ids,
+    show_settings_version_message, show_settings_written,
+    tng::{get_tng_content_name_low, get_tng_dir_and_plugin_names, process_turn_normal_grass},
+    CRC64, LAND_SIDE, SNDG_ID_MAX_LEN, SNDG_ID_SUFFIX_LEN, SNDG_MAX_SOUND_FLAG,
+};
+
+/// Run the program with command line arguments and settings file.
+///
+/// # Errors
+///
+/// Returns error if configuration is invalid or processing of any list fails.
+pub fn run() -> Result<()> {
+    let timer_total = Instant::now();
+    let cfg = config::get()?;
+    let mut log = Log::new(&cfg)?;
+    show_log_path(&cfg, &mut log)?;
+    if cfg.settings_file.write {
+        show_settings_written(&cfg, &mut log)?;
+        return Ok(());
+    }
+    show_settings_version_message(&cfg, &mut log)?;
+    prepare_plugin_cache(&cfg, &mut log).with_context(|| "Failed to prepare plugin cache")?;
+    if let Some(ref path) = cfg.unmerge {
+        return unmerge(path, &cfg, &mut log)
+            .with_context(|| format!("Failed to unmerge plugin {path:?}"));
+    }
+    if let Some(&[ref old_path, ref new_path]) = cfg.diff.as_deref() {
+        return show_plugins_diff(old_path, new_path, &cfg, &mut log)
+            .with_context(|| format!("Failed to compare plugins {old_path:?} and {new_path:?}"));
+    }
+    let mut h = Helper::new();
+    show_global_list_options(&cfg, &mut log)?;
+    let merge_override = check_presets(&mut h, &cfg, &mut log)
+        .with_context(|| "Failed to check and apply presets")?;
+    let merge = if merge_override.is_empty() {
+        &cfg.merge
+    } else {
+        &merge_override
+    };
+    if merge.is_empty() {
+        let text = "Nothing to proceed: at least one --merge or --preset-* option is required";
+        msg(text, 0, &cfg, &mut log)?;
+        return Ok(());
+    }
+    let mut output_plugin = Plugin::new();
+    let mut old_output_plugin = Plugin::new();
+    for list in merge {
+        process_list(
+            list,
+            &mut output_plugin,
+            &mut old_output_plugin,
+            &mut h,
+            &cfg,
+            &mut log,
+        )
+        .with_context(|| format!("Failed to process list \"{}\"", list.join(", ")))?;
+    }
+    h.total_commit(timer_total, &cfg, &mut log)?;
+    Ok(())
+}
+
+fn process_list(
+    list: &[String],
+    output_plugin: &mut Plugin,
+    old_output_plugin: &mut Plugin,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    if list.is_empty() {
+        msg("Skipping empty list", 0, cfg, log)?;
+        return Ok(());
+    }
+    let (index, list_options) = cfg
+        .list_options
+        .get_mutated(list, cfg, log)
+        .with_context(|| "Failed to get list options")?;
+    process_list_with_options(
+        list,
+        index,
+        list_options,
+        output_plugin,
+        old_output_plugin,
+        h,
+        cfg,
+        log,
+    )?;
+    // COMMENT: output plugin is kept after the list is processed for library users, so it's cleared here
+    output_plugin.objects.clear();
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines, clippy::too_many_arguments)]
+fn process_list_with_options(
+    list: &[String],
+    index: usize,
+    list_options: ListOptions,
+    output_plugin: &mut Plugin,
+    old_output_plugin: &mut Plugin,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let timer_global = Instant::now();
+    let mut out = Out::default();
+    let name = list
+        .first()
+        .with_context(|| "Bug: failed to get name from list")?;
+    let expanded_plugin_list = get_expanded_plugin_list(list, index, &list_options, h, cfg, log)
+        .with_context(|| "Failed to expand plugin list by scanning load order")?;
+    #[allow(clippy::shadow_same)]
+    let mut list = list;
+    if !expanded_plugin_list.is_empty() {
+        list = expanded_plugin_list
+            .get(..)
+            .with_context(|| "Bug: indexing slicing expanded_plugin_list[..]")?;
+    };
+    let regex_plugin_list = get_regex_plugin_list(list, index, &list_options, cfg, log)
+        .with_context(|| "Failed to expand plugin list with regex/glob patterns")?;
+    if !regex_plugin_list.is_empty() {
+        list = regex_plugin_list
+            .get(..)
+            .with_context(|| "Bug: indexing slicing regex_plugin_list[..]")?;
+    };
+    if should_skip_list(name, list, index, &list_options, cfg, log)? {
+        return Ok(());
+    };
+    let plugin_list = list
+        .get(index..)
+        .with_context(|| format!("Bug: indexing slicing list[{index}..]"))?;
+    let mut text: String;
+    if cfg.show_plugins {
+        text = format!(
+            "List \"{}\" contains {} files(list is ready to be copied into settings file):\n\"{}\"",
+            &name,
+            plugin_list.len(),
+            plugin_list.join("\",\n\"")
+        );
+        msg(&text, 0, cfg, log)?;
+    }
+    text = format!(
+        "Processing list \"{}\" with options: {}",
+        &name,
+        list_options.show()?
+    );
+    msg(&text, 1, cfg, log)?;
+    h.global_init(list_options);
+    let tng_content_name_low = get_tng_content_name_low(name, h, cfg)?;
+    let skip_plugin_name_low = get_skip_plugin_name_low(h);
+    let mut parsed_plugins = ParsedPlugins::new(
+        plugin_list,
+        &h.g.list_options.indirect.base_dir,
+        &tng_content_name_low,
+        &skip_plugin_name_low,
+        cfg,
+    );
+    let manifest = if h.g.list_options.incremental {
+        let current_manifest = get_manifest(&parsed_plugins, h, cfg)
+            .with_context(|| format!("Failed to get manifest for {name:?}"))?;
+        if is_list_unchanged(name, current_manifest.as_ref(), cfg, log)? {
+            return Ok(());
+        }
+        current_manifest
+    } else {
+        None
+    };
+    for (plugin_index, plugin_name) in plugin_list.iter().enumerate() {
+        if let Some(skip_reason) = parsed_plugins.get_skip_reason(plugin_index)? {
+            text = format!(
+                "  Skipped processing plugin \"{plugin_name}\" {}",
+                skip_reason.text()
+            );
+            msg(
+                &text,
+                cfg.guts.skipped_processing_plugins_msg_verbosity,
+                cfg,
+                log,
+            )?;
+            h.total_add_skipped_processing_plugin(text);
+        } else {
+            if let Err(err) = process_plugin(
+                plugin_index,
+                &mut parsed_plugins,
+                &mut out,
+                name,
+                h,
+                cfg,
+                log,
+            ) {
+                {
+                    if let Some(inner) = err.downcast_ref::<IOError>() {
+                        if matches!(inner.kind(), ErrorKind::InvalidData) {
+                            if let Some(tag) = inner.to_string().strip_prefix("Unexpected Tag: ") {
+                                if cfg
+                                    .guts
+                                    .unexpected_tags_to_ignore
+                                    .contains(&tag.to_lowercase())
+                                {
+                                    text = format!(
+                                        "  Skipped processing plugin \"{plugin_name}\" because it contains unexpected record type to ignore: \"{tag}\""
+                                    );
+                                    msg(
+                                        &text,
+                                        cfg.guts.skipped_processing_plugins_msg_verbosity,
+                                        cfg,
+                                        log,
+                                    )?;
+                                    h.total_add_skipped_processing_plugin(text);
+                                } else {
+                                    err_or_ignore(
+                                        format!("{err:#}"),
+                                        h.g.list_options.ignore_important_errors,
+                                        true,
+                                        cfg,
+                                        log,
+                                    )
+                                    .with_context(|| "Failed to process plugin")?;
+                                }
+                                continue;
+                            }
+                        };
+                    }
+                    err_or_ignore(
+                        format!("{err:#}"),
+                        h.g.list_options.ignore_important_errors,
+                        false,
+                        cfg,
+                        log,
+                    )
+                    .with_context(|| "Failed to process plugin")?;
+                    continue;
+                };
+            };
+            h.local_commit(cfg, log)?;
+        }
+    }
+    if h.g.stats.all_plugins_ignored() {
+        msg(
+            "Skipping list because all plugins were skipped",
+            0,
+            cfg,
+            log,
+        )?;
+        return Ok(());
+    }
+    process_moved_instances(&mut out, h)?;
+    if h.g.list_options.stable_ref_indices {
+        seed_ref_map(name, old_output_plugin, h, cfg, log)
+            .with_context(|| format!("Failed to seed reference indices for {name:?}"))?;
+    }
+    out = transform(name, out, h, cfg, log)?;
+    process_turn_normal_grass(name, &mut out, h, cfg, log)?;
+    make_output_plugin(name, out, output_plugin, h, cfg, log)
+        .with_context(|| format!("Failed to make output plugin {name:?}"))?;
+    write_output_plugin(name, output_plugin, old_output_plugin, 1, h, cfg, log)
+        .with_context(|| format!("Failed to write output plugin {name:?}"))?;
+    old_output_plugin.objects.clear();
+    if h.g.list_options.stable_ref_indices || h.g.list_options.write_ref_map {
+        write_ref_map(name, output_plugin, h, cfg, log)
+            .with_context(|| format!("Failed to write reference map for {name:?}"))?;
+    }
+    if h.g.list_options.write_provenance {
+        write_provenance(name, output_plugin, h, cfg, log)
+            .with_context(|| format!("Failed to write provenance file for {name:?}"))?;
+    }
+    if let Some(current_manifest) = manifest {
+        write_manifest(name, current_manifest, h, cfg, log)
+            .with_context(|| format!("Failed to write manifest file for {name:?}"))?;
+    }
+    h.global_commit(timer_global, cfg, log)
+}
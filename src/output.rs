@@ -1,5 +1,17 @@
+mod duplicate_refs;
+mod info_chain;
 pub mod make_output_plugin;
 pub mod make_turn_normal_grass;
+pub mod manifest;
+mod merge;
+mod pgrd_statics;
+pub mod provenance;
+pub mod ref_map;
 pub mod transform;
+pub mod unmerge;
 pub mod write_output_plugin;
+use duplicate_refs::process_duplicate_refs;
+use info_chain::check_info_chain;
 use make_output_plugin::make_header;
+use merge::merge;
+use pgrd_statics::check_pgrd_statics;
@@ -0,0 +1,146 @@
+use crate::{get_cell_name, show_record_notes, Cfg, Helper, Log, MastId, Out, RefrId, WarningKind};
+use anyhow::Result;
+use hashbrown::{HashMap, HashSet};
+use tes3::esp::Reference;
+
+type RefKey = (MastId, RefrId);
+
+#[allow(clippy::float_arithmetic)]
+pub fn process_duplicate_refs(
+    name: &str,
+    out: &mut Out,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let exclude = h.g.list_options.exclude_duplicate_refs;
+    let (distance, rotation) = (
+        cfg.advanced.duplicate_refs_distance,
+        cfg.advanced.duplicate_refs_rotation,
+    );
+    let mut plugin_names: HashMap<RefrId, &str> = HashMap::new();
+    for cell_meta in h.g.r.int_cells.values().chain(h.g.r.ext_cells.values()) {
+        for plugin_meta in &cell_meta.plugin_metas {
+            for plugin_refr in &plugin_meta.plugin_refrs {
+                plugin_names.insert(plugin_refr.global_refr, &plugin_meta.plugin_name_low);
+            }
+        }
+    }
+    let mut duplicates: Vec<String> = Vec::new();
+    for &mut (ref mut cell, _) in &mut out.cell {
+        let mut groups: HashMap<String, Vec<(RefKey, &Reference)>> = HashMap::new();
+        for (&key, reference) in &cell.references {
+            if reference.deleted.is_none() {
+                groups
+                    .entry(reference.id.to_lowercase())
+                    .or_default()
+                    .push((key, reference));
+            }
+        }
+        let mut cell_duplicates: Vec<String> = Vec::new();
+        let mut excluded_keys: Vec<RefKey> = Vec::new();
+        for mut group in groups.into_values() {
+            if group.len() < 2 {
+                continue;
+            }
+            // COMMENT: sorting by the first coordinate allows to only compare references that are close along it
+            group.sort_by(|&(a_key, a), &(b_key, b)| {
+                let ([a_x, ..], [b_x, ..]) = (a.translation, b.translation);
+                a_x.total_cmp(&b_x).then(a_key.cmp(&b_key))
+            });
+            let mut marked: HashSet<usize> = HashSet::new();
+            for (i, &(key, reference)) in group.iter().enumerate() {
+                if marked.contains(&i) {
+                    continue;
+                }
+                let [x, ..] = reference.translation;
+                for (j, &(other_key, other)) in group.iter().enumerate().skip(i.saturating_add(1)) {
+                    let [other_x, ..] = other.translation;
+                    if other_x - x > distance {
+                        break;
+                    }
+                    if marked.contains(&j) || !is_duplicate(reference, other, distance, rotation) {
+                        continue;
+                    }
+                    // COMMENT: references from masters are kept, otherwise the reference from the first plugin is kept
+                    let (duplicate_index, duplicate_key, original_key) = match (key.0, other_key.0)
+                    {
+                        (0, 0) if key > other_key => (Some(i), key, other_key),
+                        (_, 0) => (Some(j), other_key, key),
+                        (0, _) => (Some(i), key, other_key),
+                        _ => (None, other_key, key),
+                    };
+                    cell_duplicates.push(format!(
+                        "    CELL {}: reference {:?} from plugin \"{}\" duplicates reference from plugin \"{}\" at {:?}{}",
+                        get_cell_name(cell),
+                        reference.id,
+                        get_plugin_name(duplicate_key, &plugin_names, &out.masters),
+                        get_plugin_name(original_key, &plugin_names, &out.masters),
+                        reference.translation,
+                        if exclude && duplicate_index.is_some() {
+                            ", excluded"
+                        } else {
+                            ""
+                        }
+                    ));
+                    if let Some(duplicate_index) = duplicate_index {
+                        marked.insert(duplicate_index);
+                        if exclude {
+                            excluded_keys.push(duplicate_key);
+                        }
+                        if duplicate_index == i {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        for key in &excluded_keys {
+            cell.references.remove(key);
+        }
+        cell_duplicates.sort();
+        duplicates.append(&mut cell_duplicates);
+    }
+    let duplicates_len = duplicates.len();
+    let text = format!(
+        "  {} duplicate reference{} found in \"{}\"",
+        duplicates_len,
+        if duplicates_len == 1 {
+            " was"
+        } else {
+            "s were"
+        },
+        name
+    );
+    show_record_notes(&duplicates, text, WarningKind::DuplicateRefs, 1, cfg, log)
+}
+
+#[allow(clippy::float_arithmetic)]
+fn is_duplicate(first: &Reference, second: &Reference, distance: f32, rotation: f32) -> bool {
+    first
+        .translation
+        .iter()
+        .zip(second.translation.iter())
+        .all(|(a, b)| (a - b).abs() <= distance)
+        && first
+            .rotation
+            .iter()
+            .zip(second.rotation.iter())
+            .all(|(a, b)| (a - b).abs() <= rotation)
+}
+
+fn get_plugin_name<'a>(
+    key: RefKey,
+    plugin_names: &HashMap<RefrId, &'a str>,
+    masters: &'a [(String, u64)],
+) -> &'a str {
+    let name = if key.0 == 0 {
+        plugin_names.get(&key.1).copied()
+    } else {
+        usize::try_from(key.0)
+            .ok()
+            .and_then(|mast_index| masters.get(mast_index.saturating_sub(1)))
+            .map(|&(ref master_name, _)| &**master_name)
+    };
+    name.unwrap_or("unknown plugin")
+}
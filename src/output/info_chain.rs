@@ -0,0 +1,284 @@
+use crate::{increment, Dial, Helper};
+use anyhow::{anyhow, Context as _, Result};
+use hashbrown::HashMap;
+use tes3::esp::DialogueInfo;
+
+// COMMENT: only the last instance of each INFO id is effective, previous instances are overridden by it
+pub fn check_info_chain(dial: &mut Dial, has_masters: bool, h: &Helper) -> Result<Vec<String>> {
+    let (last_ids, order) = get_last_ids(dial);
+    let mut problems: Vec<(usize, String)> = Vec::new();
+    for id in &order {
+        let index = get_index(&last_ids, id)?;
+        let info = get_info(dial, index)?;
+        if !info.next_id.is_empty() {
+            match last_ids.get(&info.next_id) {
+                Some(&next_index) => {
+                    if get_info(dial, next_index)?.prev_id != info.id {
+                        problems.push((
+                            index,
+                            format!("next info \"{}\" doesn't link back to it", info.next_id),
+                        ));
+                    }
+                }
+                None => {
+                    if !has_masters {
+                        problems
+                            .push((index, format!("next info \"{}\" is missing", info.next_id)));
+                    }
+                }
+            }
+        }
+        if !info.prev_id.is_empty() {
+            match last_ids.get(&info.prev_id) {
+                Some(&prev_index) => {
+                    if get_info(dial, prev_index)?.next_id != info.id {
+                        problems.push((
+                            index,
+                            format!("previous info \"{}\" doesn't link back to it", info.prev_id),
+                        ));
+                    }
+                }
+                None => {
+                    if !has_masters {
+                        problems.push((
+                            index,
+                            format!("previous info \"{}\" is missing", info.prev_id),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    'cycle: for id in &order {
+        let mut current = id;
+        let mut steps: usize = 0;
+        while let Some(&index) = last_ids.get(current) {
+            let next_id = &get_info(dial, index)?.next_id;
+            if next_id.is_empty() {
+                break;
+            }
+            steps = increment!(steps);
+            if steps > order.len() {
+                problems.push((
+                    index,
+                    format!("chain of next links starting from \"{id}\" is cyclic"),
+                ));
+                break 'cycle;
+            }
+            current = next_id;
+        }
+    }
+    let repair = h.g.list_options.repair_info_chains && !problems.is_empty();
+    let mut descriptions = Vec::with_capacity(problems.len());
+    for (index, problem) in problems {
+        let plugin_id = dial
+            .info_plugin_ids
+            .get(index)
+            .with_context(|| format!("Bug: indexing slicing dial.info_plugin_ids[{index}]"))?;
+        let plugin_name = &h
+            .g
+            .plugins_processed
+            .get(*plugin_id)
+            .with_context(|| format!("Bug: indexing slicing h.g.plugins_processed[{plugin_id}]"))?
+            .name;
+        descriptions.push(format!(
+            "    Record INFO: \"{}\" from DIAL \"{}\"(plugin \"{plugin_name}\"): {problem}{}",
+            get_info(dial, index)?.id,
+            dial.dialogue.id,
+            if repair { ", repaired" } else { "" }
+        ));
+    }
+    if repair {
+        rebuild_info_chain(dial, &order, &last_ids, has_masters)?;
+    }
+    Ok(descriptions)
+}
+
+fn get_last_ids(dial: &Dial) -> (HashMap<String, usize>, Vec<String>) {
+    let mut last_ids: HashMap<String, usize> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for (index, info) in dial.info.iter().enumerate() {
+        if last_ids.insert(info.id.clone(), index).is_none() {
+            order.push(info.id.clone());
+        }
+    }
+    (last_ids, order)
+}
+
+// COMMENT: links confirmed by both sides are kept, chains made of them are linked in order of appearance instead of broken links, while links to INFOs of masters are kept
+fn rebuild_info_chain(
+    dial: &mut Dial,
+    order: &[String],
+    last_ids: &HashMap<String, usize>,
+    has_masters: bool,
+) -> Result<()> {
+    let is_external =
+        |link: &String| has_masters && !link.is_empty() && !last_ids.contains_key(link);
+    let mut confirmed_next: HashMap<String, String> = HashMap::new();
+    let mut confirmed_prev: HashMap<String, String> = HashMap::new();
+    for id in order {
+        let info = get_info(dial, get_index(last_ids, id)?)?;
+        if info.next_id == *id {
+            continue;
+        }
+        if let Some(&next_index) = last_ids.get(&info.next_id) {
+            if get_info(dial, next_index)?.prev_id == *id {
+                confirmed_next.insert(id.clone(), info.next_id.clone());
+                confirmed_prev.insert(info.next_id.clone(), id.clone());
+            }
+        }
+    }
+    for id in order {
+        let mut current = id;
+        let mut steps: usize = 0;
+        let mut is_cyclic = false;
+        while let Some(prev_id) = confirmed_prev.get(current) {
+            steps = increment!(steps);
+            if prev_id == id || steps > order.len() {
+                is_cyclic = true;
+                break;
+            }
+            current = prev_id;
+        }
+        if is_cyclic {
+            if let Some(prev_id) = confirmed_prev.remove(id) {
+                confirmed_next.remove(&prev_id);
+            }
+        }
+    }
+    let mut chains: Vec<(String, String, bool, bool)> = Vec::new();
+    for id in order {
+        if confirmed_prev.contains_key(id) {
+            continue;
+        }
+        let mut tail = id;
+        while let Some(next_id) = confirmed_next.get(tail) {
+            tail = next_id;
+        }
+        chains.push((
+            id.clone(),
+            tail.clone(),
+            is_external(&get_info(dial, get_index(last_ids, id)?)?.prev_id),
+            is_external(&get_info(dial, get_index(last_ids, tail)?)?.next_id),
+        ));
+    }
+    for (position, &(ref head, ref tail, is_head_external, is_tail_external)) in
+        chains.iter().enumerate()
+    {
+        if !is_head_external {
+            let prev_id = match position.checked_sub(1).and_then(|prev| chains.get(prev)) {
+                Some(&(_, ref prev_tail, _, false)) => prev_tail.clone(),
+                Some(_) | None => String::new(),
+            };
+            get_info_mut(dial, get_index(last_ids, head)?)?.prev_id = prev_id;
+        }
+        if !is_tail_external {
+            let next_id = match chains.get(increment!(position)) {
+                Some(&(ref next_head, _, false, _)) => next_head.clone(),
+                Some(_) | None => String::new(),
+            };
+            get_info_mut(dial, get_index(last_ids, tail)?)?.next_id = next_id;
+        }
+    }
+    Ok(())
+}
+
+fn get_index(last_ids: &HashMap<String, usize>, id: &str) -> Result<usize> {
+    last_ids
+        .get(id)
+        .copied()
+        .ok_or_else(|| anyhow!("Bug: failed to find INFO \"{id}\" in last_ids"))
+}
+
+fn get_info(dial: &Dial, index: usize) -> Result<&DialogueInfo> {
+    dial.info
+        .get(index)
+        .with_context(|| format!("Bug: indexing slicing dial.info[{index}]"))
+}
+
+fn get_info_mut(dial: &mut Dial, index: usize) -> Result<&mut DialogueInfo> {
+    dial.info
+        .get_mut(index)
+        .with_context(|| format!("Bug: indexing slicing dial.info[{index}]"))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{get_last_ids, rebuild_info_chain};
+    use crate::Dial;
+    use tes3::esp::{Dialogue, DialogueInfo};
+
+    fn get_dial(links: &[(&str, &str, &str)]) -> Dial {
+        let mut dial = Dial::new(Dialogue {
+            id: "test_topic".to_owned(),
+            ..Default::default()
+        });
+        for &(prev_id, id, next_id) in links {
+            dial.info.push(DialogueInfo {
+                id: id.to_owned(),
+                prev_id: prev_id.to_owned(),
+                next_id: next_id.to_owned(),
+                ..Default::default()
+            });
+            dial.info_plugin_ids.push(0);
+        }
+        dial
+    }
+
+    fn repair(dial: &mut Dial, has_masters: bool) -> Vec<(String, String, String)> {
+        let (last_ids, order) = get_last_ids(dial);
+        rebuild_info_chain(dial, &order, &last_ids, has_masters).expect("failed to repair");
+        dial.info
+            .iter()
+            .map(|info| (info.prev_id.clone(), info.id.clone(), info.next_id.clone()))
+            .collect()
+    }
+
+    fn links(links: &[(&str, &str, &str)]) -> Vec<(String, String, String)> {
+        links
+            .iter()
+            .map(|&(prev_id, id, next_id)| (prev_id.to_owned(), id.to_owned(), next_id.to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn only_broken_links_are_repaired() {
+        let mut dial = get_dial(&[
+            ("", "a", "b"),
+            ("a", "b", "missing"),
+            ("c", "d", ""),
+            ("b", "c", "d"),
+        ]);
+        assert_eq!(
+            repair(&mut dial, false),
+            links(&[
+                ("", "a", "b"),
+                ("a", "b", "c"),
+                ("c", "d", ""),
+                ("b", "c", "d"),
+            ]),
+            "valid links should be kept, while broken link should be replaced"
+        );
+    }
+
+    #[test]
+    fn links_to_master_infos_are_kept() {
+        let mut dial = get_dial(&[
+            ("master_1", "a", "b"),
+            ("a", "b", "master_2"),
+            ("", "c", "a"),
+            ("b", "d", ""),
+        ]);
+        assert_eq!(
+            repair(&mut dial, true),
+            links(&[
+                ("master_1", "a", "b"),
+                ("a", "b", "master_2"),
+                ("", "c", "d"),
+                ("c", "d", ""),
+            ]),
+            "links to INFOs of masters should be kept"
+        );
+    }
+}
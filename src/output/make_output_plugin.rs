@@ -1,8 +1,11 @@
+use super::check_info_chain;
 use crate::{
-    get_cell_name, msg, select_header_description, show_removed_record_ids, Cfg, Dial, HeaderText,
-    Helper, Log, Mode, Out, StatsUpdateKind,
+    get_cell_name, msg, select_header_description, show_record_notes, show_removed_record_ids, Cfg,
+    Dial, HeaderText, Helper, Log, Mode, Out, RecordRule, RecordRuleAction, StatsUpdateKind,
+    WarningKind,
 };
 use anyhow::Result;
+use std::mem::take;
 use tes3::esp::{DialogueType2, FixedString, Header, ObjectFlags, Plugin, TES3Object};
 
 #[allow(clippy::too_many_lines, clippy::cognitive_complexity)]
@@ -21,12 +24,23 @@ pub fn make_output_plugin(
         Mode::Keep
     };
     let mut removed_record_ids = Vec::new();
+    let mut rule_removed_record_ids = take(&mut h.g.record_rules_ignored);
     macro_rules! move_out {
-        ($type:ident, $obj:ident, $mode:expr) => {
+        ($type:ident, $obj:ident, $mode:expr $(, $id:ident)?) => {
             let type_str = stringify!($type);
             for (last, prevs) in out.$type.into_iter() {
                 let prevs_len = prevs.len();
-                if h.g.list_options.exclude_deleted_records && last.flags.contains(ObjectFlags::DELETED) {
+                #[allow(unused_mut)]
+                let mut rule: Option<&RecordRule> = None;
+                $(if !cfg.advanced.record_rules.is_empty() {
+                    rule = cfg.advanced.record_rules.get(type_str, &last.$id.to_lowercase());
+                })?
+                if let Some(&RecordRule { action: RecordRuleAction::Exclude, ref reason }) = rule {
+                    let removed_record_id = get_removed_record_id(TES3Object::$obj(last.clone()));
+                    let text = format!("    Record {}: {removed_record_id} was excluded from the result due to exclude rule\n      Reason: {reason}", type_str.to_uppercase());
+                    rule_removed_record_ids.push(text);
+                    h.g.stats.$type(StatsUpdateKind::Excluded);
+                } else if h.g.list_options.exclude_deleted_records && last.flags.contains(ObjectFlags::DELETED) {
                     let removed_record_id = get_removed_record_id(TES3Object::$obj(last.clone()));
                     let text = format!("    Record {}: {removed_record_id} was excluded from the result due to \"DELETED\" flag", type_str.to_uppercase());
                     removed_record_ids.push(text);
@@ -40,10 +54,15 @@ pub fn make_output_plugin(
                                 h.g.stats.$type(StatsUpdateKind::ResultMergeableTotal);
                             }
                         } else {
-                            match $mode {
+                            let mode = if matches!(rule, Some(&RecordRule { action: RecordRuleAction::KeepPrevious, .. })) {
+                                &Mode::Keep
+                            } else {
+                                &$mode
+                            };
+                            match mode {
                                 Mode::Replace | Mode::CompleteReplace | Mode::Grass => {}
                                 _ => {
-                                    match $mode {
+                                    match mode {
                                         Mode::KeepWithoutLands | Mode::JobashaWithoutLands if type_str == "land" => {}
                                         // COMMENT: Mode::Jobasha | Mode::JobashaWithoutLands if type_str == "gmst" || type_str == "clas" => {}
                                         _ => {
@@ -67,47 +86,57 @@ pub fn make_output_plugin(
             }
         };
     }
-    move_out!(gmst, GameSetting, h.g.list_options.mode);
-    move_out!(glob, GlobalVariable, h.g.list_options.mode);
-    move_out!(clas, Class, h.g.list_options.mode);
-    move_out!(fact, Faction, h.g.list_options.mode);
-    move_out!(race, Race, h.g.list_options.mode);
-    move_out!(soun, Sound, h.g.list_options.mode);
-    move_out!(sndg, SoundGen, h.g.list_options.mode);
+    move_out!(gmst, GameSetting, h.g.list_options.mode, id);
+    move_out!(glob, GlobalVariable, h.g.list_options.mode, id);
+    move_out!(clas, Class, h.g.list_options.mode, id);
+    move_out!(fact, Faction, h.g.list_options.mode, id);
+    move_out!(race, Race, h.g.list_options.mode, id);
+    move_out!(soun, Sound, h.g.list_options.mode, id);
+    move_out!(sndg, SoundGen, h.g.list_options.mode, id);
     move_out!(skil, Skill, h.g.list_options.mode);
     move_out!(mgef, MagicEffect, h.g.list_options.mode);
-    move_out!(scpt, Script, h.g.list_options.mode);
-    move_out!(regn, Region, h.g.list_options.mode);
-    move_out!(bsgn, Birthsign, h.g.list_options.mode);
-    move_out!(sscr, StartScript, h.g.list_options.mode);
+    move_out!(scpt, Script, h.g.list_options.mode, id);
+    move_out!(regn, Region, h.g.list_options.mode, id);
+    move_out!(bsgn, Birthsign, h.g.list_options.mode, id);
+    move_out!(sscr, StartScript, h.g.list_options.mode, id);
     move_out!(ltex, LandscapeTexture, h.g.list_options.mode);
-    move_out!(spel, Spell, h.g.list_options.mode);
-    move_out!(stat, Static, h.g.list_options.mode);
-    move_out!(door, Door, h.g.list_options.mode);
-    move_out!(misc, MiscItem, h.g.list_options.mode);
-    move_out!(weap, Weapon, h.g.list_options.mode);
-    move_out!(cont, Container, h.g.list_options.mode);
-    move_out!(crea, Creature, h.g.list_options.mode);
-    move_out!(body, Bodypart, h.g.list_options.mode);
-    move_out!(ligh, Light, h.g.list_options.mode);
-    move_out!(ench, Enchanting, h.g.list_options.mode);
-    move_out!(npc_, Npc, h.g.list_options.mode);
-    move_out!(armo, Armor, h.g.list_options.mode);
-    move_out!(clot, Clothing, h.g.list_options.mode);
-    move_out!(repa, RepairItem, h.g.list_options.mode);
-    move_out!(acti, Activator, h.g.list_options.mode);
-    move_out!(appa, Apparatus, h.g.list_options.mode);
-    move_out!(lock, Lockpick, h.g.list_options.mode);
-    move_out!(prob, Probe, h.g.list_options.mode);
-    move_out!(ingr, Ingredient, h.g.list_options.mode);
-    move_out!(book, Book, h.g.list_options.mode);
-    move_out!(alch, Alchemy, h.g.list_options.mode);
-    move_out!(levi, LeveledItem, lev_mode);
-    move_out!(levc, LeveledCreature, lev_mode);
+    move_out!(spel, Spell, h.g.list_options.mode, id);
+    move_out!(stat, Static, h.g.list_options.mode, id);
+    move_out!(door, Door, h.g.list_options.mode, id);
+    move_out!(misc, MiscItem, h.g.list_options.mode, id);
+    move_out!(weap, Weapon, h.g.list_options.mode, id);
+    move_out!(cont, Container, h.g.list_options.mode, id);
+    move_out!(crea, Creature, h.g.list_options.mode, id);
+    move_out!(body, Bodypart, h.g.list_options.mode, id);
+    move_out!(ligh, Light, h.g.list_options.mode, id);
+    move_out!(ench, Enchanting, h.g.list_options.mode, id);
+    move_out!(npc_, Npc, h.g.list_options.mode, id);
+    move_out!(armo, Armor, h.g.list_options.mode, id);
+    move_out!(clot, Clothing, h.g.list_options.mode, id);
+    move_out!(repa, RepairItem, h.g.list_options.mode, id);
+    move_out!(acti, Activator, h.g.list_options.mode, id);
+    move_out!(appa, Apparatus, h.g.list_options.mode, id);
+    move_out!(lock, Lockpick, h.g.list_options.mode, id);
+    move_out!(prob, Probe, h.g.list_options.mode, id);
+    move_out!(ingr, Ingredient, h.g.list_options.mode, id);
+    move_out!(book, Book, h.g.list_options.mode, id);
+    move_out!(alch, Alchemy, h.g.list_options.mode, id);
+    move_out!(levi, LeveledItem, lev_mode, id);
+    move_out!(levc, LeveledCreature, lev_mode, id);
     move_out!(cell, Cell, h.g.list_options.mode);
     move_out!(land, Landscape, h.g.list_options.mode);
-    move_out!(pgrd, PathGrid, h.g.list_options.mode);
-    move_out_dial(out.dial, &mut objects, h);
+    move_out!(pgrd, PathGrid, h.g.list_options.mode, cell);
+    let has_masters = !out.masters.is_empty();
+    move_out_dial(name, out.dial, &mut objects, has_masters, h, cfg, log)?;
+    show_missing_rule_winners(name, h, cfg, log)?;
+    show_removed_record_ids(
+        &rule_removed_record_ids,
+        "settings.advanced.record_rules",
+        name,
+        1,
+        cfg,
+        log,
+    )?;
     if h.g.list_options.exclude_deleted_records && !removed_record_ids.is_empty() {
         let reason = "\"exclude_deleted_records\" and DELETED record flag";
         show_removed_record_ids(&removed_record_ids, reason, name, 1, cfg, log)?;
@@ -157,6 +186,43 @@ pub fn make_header(
     })
 }
 
+fn show_missing_rule_winners(name: &str, h: &Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut missing_winners: Vec<String> = h
+        .g
+        .r
+        .record_rule_winner_plugins
+        .iter()
+        .filter(|&(_, plugin_name_low)| {
+            !h.g.plugins_processed
+                .iter()
+                .any(|plugin_info| plugin_info.name_low == *plugin_name_low)
+        })
+        .map(|(&(record_type, ref id_low), plugin_name_low)| {
+            format!(
+                "    Record {}: winning plugin \"{plugin_name_low}\" of \"{id_low}\" is not in the list",
+                record_type.to_uppercase()
+            )
+        })
+        .collect();
+    missing_winners.sort();
+    let missing_winners_len = missing_winners.len();
+    let text = format!(
+        "  Warning: {} winning plugin rule{} of settings.advanced.record_rules {} not applied to \"{}\" because winning plugin is not in the list",
+        missing_winners_len,
+        if missing_winners_len == 1 { "" } else { "s" },
+        if missing_winners_len == 1 { "was" } else { "were" },
+        name
+    );
+    show_record_notes(
+        &missing_winners,
+        text,
+        WarningKind::MissingRuleWinners,
+        1,
+        cfg,
+        log,
+    )
+}
+
 fn get_removed_record_id(tes3object: TES3Object) -> String {
     match tes3object {
         TES3Object::Header(v) => format!("{:?}", v.description),
@@ -205,11 +271,21 @@ fn get_removed_record_id(tes3object: TES3Object) -> String {
     }
 }
 
-fn move_out_dial(out_dial: Vec<(Dial, Vec<Dial>)>, objects: &mut Vec<TES3Object>, h: &mut Helper) {
+fn move_out_dial(
+    name: &str,
+    out_dial: Vec<(Dial, Vec<Dial>)>,
+    objects: &mut Vec<TES3Object>,
+    has_masters: bool,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
     let mut is_journal: bool;
     let mut journal = Vec::new();
     let mut non_journal = Vec::new();
-    for dial in out_dial {
+    let mut info_chain_problems = Vec::new();
+    for mut dial in out_dial {
+        info_chain_problems.append(&mut check_info_chain(&mut dial.0, has_masters, h)?);
         if dial.0.dialogue.dialogue_type == DialogueType2::Journal {
             is_journal = true;
             journal.push(TES3Object::Dialogue(dial.0.dialogue));
@@ -229,4 +305,24 @@ fn move_out_dial(out_dial: Vec<(Dial, Vec<Dial>)>, objects: &mut Vec<TES3Object>
     }
     objects.append(&mut journal);
     objects.append(&mut non_journal);
+    let problems_len = info_chain_problems.len();
+    let text = format!(
+        "  {} INFO chain problem{} {} in \"{}\"",
+        problems_len,
+        if problems_len == 1 { " was" } else { "s were" },
+        if h.g.list_options.repair_info_chains {
+            "repaired"
+        } else {
+            "found"
+        },
+        name
+    );
+    show_record_notes(
+        &info_chain_problems,
+        text,
+        WarningKind::InfoChainProblems,
+        1,
+        cfg,
+        log,
+    )
 }
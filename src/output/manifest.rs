@@ -0,0 +1,247 @@
+use crate::{msg, Cfg, Helper, Log, ManifestFile, ManifestInput, ParsedPlugins};
+use anyhow::{Context as _, Result};
+use fs_err::{metadata, read_to_string, write};
+use std::{path::Path, time::UNIX_EPOCH};
+
+// COMMENT: output plugin is added to the manifest only after it's written
+pub fn get_manifest(
+    parsed_plugins: &ParsedPlugins,
+    h: &Helper,
+    cfg: &Cfg,
+) -> Result<Option<ManifestFile>> {
+    let Some(plugins) = parsed_plugins
+        .paths
+        .iter()
+        .flatten()
+        .map(|&(ref plugin_pathbuf, _)| get_manifest_input(plugin_pathbuf))
+        .collect::<Option<Vec<ManifestInput>>>()
+    else {
+        return Ok(None);
+    };
+    Ok(Some(ManifestFile {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        options: h.g.list_options.show()?,
+        settings: get_manifest_input(&cfg.settings_file.path),
+        output: None,
+        plugins,
+    }))
+}
+
+pub fn is_list_unchanged(
+    name: &str,
+    manifest: Option<&ManifestFile>,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<bool> {
+    let manifest_name = format!("{name}{}", cfg.guts.manifest_suffix);
+    let Some(reason) = get_rebuild_reason(name, &manifest_name, manifest) else {
+        let text = format!(
+            "Output plugin {name:?} processing skipped because its inputs didn't change since the previous run"
+        );
+        msg(text, 0, cfg, log)?;
+        return Ok(true);
+    };
+    let text = format!("Output plugin {name:?} is rebuilt because {reason}");
+    msg(text, 0, cfg, log)?;
+    Ok(false)
+}
+
+pub fn write_manifest(
+    name: &str,
+    mut manifest: ManifestFile,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let manifest_name = format!("{name}{}", cfg.guts.manifest_suffix);
+    let text = if h.g.list_options.dry_run {
+        format!("Manifest file {manifest_name:?} would be written")
+    } else {
+        manifest.output = get_manifest_input(Path::new(name));
+        write(
+            &manifest_name,
+            toml::to_string(&manifest)
+                .with_context(|| format!("Failed to serialize manifest file {manifest_name:?}"))?,
+        )
+        .with_context(|| format!("Failed to write manifest file {manifest_name:?}"))?;
+        format!("Manifest file {manifest_name:?} was written")
+    };
+    msg(text, 1, cfg, log)
+}
+
+fn get_rebuild_reason(
+    name: &str,
+    manifest_name: &str,
+    manifest: Option<&ManifestFile>,
+) -> Option<String> {
+    let Some(current) = manifest else {
+        return Some("some of the plugins can't be read".to_owned());
+    };
+    if !Path::new(manifest_name).exists() {
+        return Some(format!("manifest file {manifest_name:?} doesn't exist"));
+    }
+    let Some(previous) = read_to_string(manifest_name)
+        .ok()
+        .and_then(|contents| toml::from_str::<ManifestFile>(&contents).ok())
+    else {
+        return Some(format!("manifest file {manifest_name:?} can't be read"));
+    };
+    if previous.version != current.version {
+        return Some(format!(
+            "program version changed from \"{}\" to \"{}\"",
+            previous.version, current.version
+        ));
+    }
+    if previous.options != current.options {
+        return Some("list options changed".to_owned());
+    }
+    if previous.settings != current.settings {
+        return Some("settings file changed".to_owned());
+    }
+    if previous.plugins.len() != current.plugins.len()
+        || previous
+            .plugins
+            .iter()
+            .zip(&current.plugins)
+            .any(|(old, new)| old.path != new.path)
+    {
+        return Some("list of plugins changed".to_owned());
+    }
+    if let Some(changed) = previous
+        .plugins
+        .iter()
+        .zip(&current.plugins)
+        .find_map(|(old, new)| (old != new).then_some(&new.path))
+    {
+        return Some(format!("plugin \"{changed}\" changed"));
+    }
+    match previous.output {
+        None => Some("output plugin wasn't written during the previous run".to_owned()),
+        Some(ref output) if get_manifest_input(Path::new(name)).as_ref() != Some(output) => {
+            Some("output plugin changed or is missing".to_owned())
+        }
+        Some(_) => None,
+    }
+}
+
+fn get_manifest_input(path: &Path) -> Option<ManifestInput> {
+    let file_metadata = metadata(path).ok()?;
+    let mtime = file_metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?;
+    Some(ManifestInput {
+        path: path.to_string_lossy().into_owned(),
+        size: file_metadata.len(),
+        mtime_secs: mtime.as_secs(),
+        mtime_nanos: mtime.subsec_nanos(),
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{get_manifest_input, get_rebuild_reason};
+    use crate::ManifestFile;
+    use fs_err::{create_dir_all, remove_dir_all, write};
+    use std::{env::temp_dir, path::Path, process};
+
+    fn get_manifest_file(dir: &Path, output: bool) -> ManifestFile {
+        ManifestFile {
+            version: "1.0.0".to_owned(),
+            options: "options".to_owned(),
+            settings: None,
+            output: output
+                .then(|| get_manifest_input(&dir.join("Out.esp")))
+                .flatten(),
+            plugins: vec![
+                get_manifest_input(&dir.join("Plugin.esp")).expect("failed to get plugin metadata")
+            ],
+        }
+    }
+
+    fn get_reason(dir: &Path, current: &ManifestFile) -> Option<String> {
+        let name = dir.join("Out.esp").to_string_lossy().into_owned();
+        let manifest_name = format!("{name}.manifest.toml");
+        get_rebuild_reason(&name, &manifest_name, Some(current))
+    }
+
+    #[test]
+    fn rebuild_reasons() {
+        let dir = temp_dir().join(format!("habasi_manifest_test_{}", process::id()));
+        create_dir_all(&dir).expect("failed to create test directory");
+        write(dir.join("Plugin.esp"), "plugin").expect("failed to write plugin");
+        write(dir.join("Out.esp"), "output").expect("failed to write output plugin");
+        let reason_missing = get_reason(&dir, &get_manifest_file(&dir, false));
+        write(
+            dir.join("Out.esp.manifest.toml"),
+            toml::to_string(&get_manifest_file(&dir, true)).expect("failed to serialize"),
+        )
+        .expect("failed to write manifest");
+        let reason_unchanged = get_reason(&dir, &get_manifest_file(&dir, false));
+        let reason_version = get_reason(
+            &dir,
+            &ManifestFile {
+                version: "2.0.0".to_owned(),
+                ..get_manifest_file(&dir, false)
+            },
+        );
+        let reason_options = get_reason(
+            &dir,
+            &ManifestFile {
+                options: "other".to_owned(),
+                ..get_manifest_file(&dir, false)
+            },
+        );
+        let reason_list = get_reason(
+            &dir,
+            &ManifestFile {
+                plugins: Vec::new(),
+                ..get_manifest_file(&dir, false)
+            },
+        );
+        write(dir.join("Out.esp"), "changed output").expect("failed to write output plugin");
+        let reason_output = get_reason(&dir, &get_manifest_file(&dir, false));
+        write(dir.join("Plugin.esp"), "changed plugin").expect("failed to write plugin");
+        let reason_plugin = get_reason(&dir, &get_manifest_file(&dir, false));
+        remove_dir_all(&dir).expect("failed to remove test directory");
+        assert!(
+            reason_missing.is_some_and(|reason| reason.starts_with("manifest file")),
+            "missing manifest should be a reason"
+        );
+        assert_eq!(reason_unchanged, None, "unchanged inputs should be skipped");
+        assert!(
+            reason_version.is_some_and(|reason| reason.starts_with("program version")),
+            "changed version should be a reason"
+        );
+        assert_eq!(
+            reason_options.as_deref(),
+            Some("list options changed"),
+            "changed options should be a reason"
+        );
+        assert_eq!(
+            reason_list.as_deref(),
+            Some("list of plugins changed"),
+            "changed list of plugins should be a reason"
+        );
+        assert_eq!(
+            reason_output.as_deref(),
+            Some("output plugin changed or is missing"),
+            "changed output plugin should be a reason"
+        );
+        assert!(
+            reason_plugin.is_some_and(|reason| reason.ends_with("Plugin.esp\" changed")),
+            "changed plugin should be a reason"
+        );
+    }
+
+    #[test]
+    fn unreadable_plugins_are_a_reason() {
+        assert_eq!(
+            get_rebuild_reason("Out.esp", "Out.esp.manifest.toml", None).as_deref(),
+            Some("some of the plugins can't be read"),
+            "manifest that can't be made should be a reason"
+        );
+    }
+}
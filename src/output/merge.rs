@@ -0,0 +1,126 @@
+use crate::{show_record_notes, Cfg, Helper, Log, Out, RecordRule, RecordRuleAction, WarningKind};
+use anyhow::Result;
+mod actor;
+mod land;
+mod lev;
+mod race;
+
+pub fn merge(name: &str, out: &mut Out, h: &mut Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    if h.g.list_options.merge_leveled_lists {
+        lev::merge(name, out, h, cfg, log)?;
+    }
+    if h.g.list_options.merge_actors {
+        actor::merge(name, out, h, cfg, log)?;
+    }
+    if h.g.list_options.merge_races_birthsigns {
+        race::merge(name, out, h, cfg, log)?;
+    }
+    if h.g.list_options.merge_lands {
+        land::merge(name, out, h, cfg, log)?;
+    }
+    Ok(())
+}
+
+// COMMENT: versions of the record with keep previous rule are kept as is instead of being merged into one, version of the winning plugin found in the list is taken as is
+fn is_merge_skipped(record_type: &'static str, id: &str, h: &Helper, cfg: &Cfg) -> bool {
+    if cfg.advanced.record_rules.is_empty() {
+        return false;
+    }
+    let id_low = id.to_lowercase();
+    match cfg.advanced.record_rules.get(record_type, &id_low) {
+        Some(&RecordRule {
+            action: RecordRuleAction::KeepPrevious,
+            ..
+        }) => true,
+        Some(&RecordRule {
+            action: RecordRuleAction::Winner(_),
+            ..
+        }) => h.g.r.record_rule_winners.contains(&(record_type, id_low)),
+        Some(&RecordRule {
+            action: RecordRuleAction::Exclude,
+            ..
+        })
+        | None => false,
+    }
+}
+
+fn last_changed<T: PartialEq + Copy, I: Iterator<Item = T>>(base: T, versions: I) -> T {
+    versions.fold(
+        base,
+        |acc, version| if version == base { acc } else { version },
+    )
+}
+
+// COMMENT: the last version that differs from the base wins, conflict is returned when versions changed the field differently
+fn merge_field<'a, T: PartialEq + Clone + 'a, I: Iterator<Item = &'a T>>(
+    merged: &mut T,
+    base: &T,
+    versions: I,
+) -> bool {
+    let mut winner: Option<&T> = None;
+    let mut conflict = false;
+    for version in versions.filter(|&version| version != base) {
+        if winner.is_some_and(|winner| winner != version) {
+            conflict = true;
+        }
+        winner = Some(version);
+    }
+    if let Some(winner) = winner {
+        merged.clone_from(winner);
+    }
+    conflict
+}
+
+fn show_conflicts(
+    conflicts: &[String],
+    kind: &str,
+    name: &str,
+    verbosity: u8,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let conflicts_len = conflicts.len();
+    let text = format!(
+        "  {} conflict{} found while merging {} of \"{}\"",
+        conflicts_len,
+        if conflicts_len == 1 { " was" } else { "s were" },
+        kind,
+        name
+    );
+    show_record_notes(
+        conflicts,
+        text,
+        WarningKind::MergeConflicts,
+        verbosity,
+        cfg,
+        log,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::last_changed;
+
+    #[test]
+    fn last_changed_version_wins() {
+        assert_eq!(
+            last_changed(1, [2, 1, 3, 1].into_iter()),
+            3,
+            "last value that differs from the base should win"
+        );
+    }
+
+    #[test]
+    fn unchanged_versions_keep_base() {
+        assert_eq!(
+            last_changed(1, [1, 1].into_iter()),
+            1,
+            "base should be kept when no version changes it"
+        );
+        assert_eq!(
+            last_changed(1, [].into_iter()),
+            1,
+            "base should be kept without versions"
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use super::{is_merge_skipped, merge_field, show_conflicts};
+use crate::{increment, msg, Cfg, Helper, Log, Out};
+use anyhow::{Context as _, Result};
+
+pub fn merge(name: &str, out: &mut Out, h: &Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut merged: usize = 0;
+    let mut conflicts: Vec<String> = Vec::new();
+    macro_rules! merge_fields {
+        ($tag:literal, $merged:ident, $base:ident, $versions:ident, $($($field:ident).+),+) => {
+            $(
+                if merge_field(
+                    &mut $merged.$($field).+,
+                    &$base.$($field).+,
+                    $versions.iter().map(|version| &version.$($field).+),
+                ) {
+                    conflicts.push(format!(
+                        "    {} \"{}\": {} changed by several plugins, the last change was kept",
+                        $tag,
+                        $merged.id,
+                        stringify!($($field).+)
+                    ));
+                }
+            )+
+        };
+    }
+    macro_rules! merge_actor {
+        ($type:ident, $tag:literal, $flags:ident, [$($data:ident),+], [$($other:ident),+]) => {
+            for &mut (ref mut last, ref mut prevs) in &mut out.$type {
+                if is_merge_skipped(stringify!($type), &last.id, h, cfg) {
+                    continue;
+                }
+                if let Some((base, versions)) = prevs.split_first() {
+                    let mut merged_record = base.clone();
+                    merged_record.id.clone_from(&last.id);
+                    merge_fields!($tag, merged_record, base, versions, flags, $flags, blood_type);
+                    merge_fields!($tag, merged_record, base, versions, $(data.$data),+);
+                    merge_fields!(
+                        $tag,
+                        merged_record,
+                        base,
+                        versions,
+                        ai_data.hello,
+                        ai_data.fight,
+                        ai_data.flee,
+                        ai_data.alarm,
+                        ai_data.services,
+                        ai_packages
+                    );
+                    if h.g.list_options.merge_inventories {
+                        merged_record.inventory.clone_from(&last.inventory);
+                        merged_record.spells.clone_from(&last.spells);
+                        merged_record.travel_destinations.clone_from(&last.travel_destinations);
+                    } else {
+                        merge_fields!($tag, merged_record, base, versions, inventory, spells, travel_destinations);
+                    }
+                    merge_fields!($tag, merged_record, base, versions, $($other),+);
+                    *last = merged_record;
+                    if !h.g.list_options.debug {
+                        prevs.clear();
+                    }
+                    merged = increment!(merged);
+                }
+            }
+        };
+    }
+    merge_actor!(
+        npc_,
+        "NPC_",
+        npc_flags,
+        [level, stats, disposition, reputation, rank, gold],
+        [name, mesh, race, class, faction, head, hair, script]
+    );
+    merge_actor!(
+        crea,
+        "CREA",
+        creature_flags,
+        [
+            creature_type,
+            level,
+            attributes,
+            health,
+            magicka,
+            fatigue,
+            soul_points,
+            combat,
+            magic,
+            stealth,
+            attack1,
+            attack2,
+            attack3,
+            gold
+        ],
+        [name, mesh, script, sound, scale]
+    );
+    if merged > 0 {
+        let text = format!("Output plugin {name:?}: {merged} NPC and creature records merged");
+        msg(text, 1, cfg, log)?;
+    }
+    show_conflicts(&conflicts, "NPC and creature records", name, 1, cfg, log)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::merge;
+    use crate::{get_test_cfg_and_log, Helper, Out, WarningKind};
+    use tes3::esp::Npc;
+
+    fn get_npc(name: &str, hair: &str, level: i16) -> Npc {
+        let mut npc = Npc {
+            id: "test_npc".to_owned(),
+            name: name.to_owned(),
+            hair: hair.to_owned(),
+            ..Default::default()
+        };
+        npc.data.level = level;
+        npc
+    }
+
+    fn run_merge(base: Npc, versions: Vec<Npc>) -> (Npc, Vec<WarningKind>) {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let mut out = Out::default();
+        let last = versions.last().cloned().unwrap_or_else(|| base.clone());
+        let mut prevs = vec![base];
+        prevs.extend(versions);
+        out.npc_.push((last, prevs));
+        merge("Test.esp", &mut out, &Helper::new(), &cfg, &mut log).expect("failed to merge");
+        let (merged, _) = out.npc_.pop().expect("merged record is missing");
+        let kinds = log
+            .warnings
+            .unwrap_or_default()
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect();
+        (merged, kinds)
+    }
+
+    #[test]
+    fn changes_to_different_fields_are_combined() {
+        let (merged, kinds) = run_merge(
+            get_npc("Base", "base_hair", 1),
+            vec![
+                get_npc("Changed", "base_hair", 1),
+                get_npc("Base", "new_hair", 1),
+                get_npc("Base", "base_hair", 5),
+            ],
+        );
+        assert_eq!(merged.name, "Changed", "name change was lost");
+        assert_eq!(merged.hair, "new_hair", "hair change was lost");
+        assert_eq!(merged.data.level, 5, "level change was lost");
+        assert!(kinds.is_empty(), "changes without conflicts were reported");
+    }
+
+    #[test]
+    fn conflicting_changes_keep_the_last_one() {
+        let (merged, kinds) = run_merge(
+            get_npc("Base", "base_hair", 1),
+            vec![
+                get_npc("First", "base_hair", 1),
+                get_npc("Second", "new_hair", 1),
+            ],
+        );
+        assert_eq!(merged.name, "Second", "the last change should be kept");
+        assert_eq!(merged.hair, "new_hair", "hair change was lost");
+        assert_eq!(
+            kinds,
+            [WarningKind::MergeConflicts],
+            "conflict wasn't reported"
+        );
+    }
+
+    #[test]
+    fn equal_changes_are_not_conflicts() {
+        let (merged, kinds) = run_merge(
+            get_npc("Base", "base_hair", 1),
+            vec![
+                get_npc("Same", "base_hair", 1),
+                get_npc("Same", "base_hair", 1),
+            ],
+        );
+        assert_eq!(merged.name, "Same", "equal changes should be kept");
+        assert!(
+            kinds.is_empty(),
+            "equal changes were reported as a conflict"
+        );
+    }
+
+    #[test]
+    fn reverted_field_keeps_earlier_change() {
+        let (merged, kinds) = run_merge(
+            get_npc("Base", "base_hair", 1),
+            vec![
+                get_npc("Changed", "base_hair", 1),
+                get_npc("Base", "base_hair", 1),
+            ],
+        );
+        assert_eq!(
+            merged.name, "Changed",
+            "version equal to the base shouldn't revert the change"
+        );
+        assert!(kinds.is_empty(), "single change was reported as a conflict");
+    }
+}
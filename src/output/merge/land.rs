@@ -0,0 +1,189 @@
+use super::{merge_field, show_conflicts};
+use crate::{increment, msg, Cfg, Helper, Log, Out};
+use anyhow::{Context as _, Result};
+use tes3::esp::LandscapeFlags;
+
+pub fn merge(name: &str, out: &mut Out, h: &Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut merged: usize = 0;
+    let mut conflicts: Vec<String> = Vec::new();
+    for &mut (ref mut last, ref mut prevs) in &mut out.land {
+        if let Some((base, versions)) = prevs.split_first() {
+            let mut merged_land = base.clone();
+            // COMMENT: versions without the subrecord(flag is unset, data is default) don't change it
+            macro_rules! merge_subrecord {
+                ($field:ident, $subrecord:literal, $flag:expr) => {
+                    let flag: Option<LandscapeFlags> = $flag;
+                    if merge_field(
+                        &mut merged_land.$field,
+                        &base.$field,
+                        versions
+                            .iter()
+                            .filter(|version| {
+                                flag.map_or(true, |flag| version.landscape_flags.contains(flag))
+                            })
+                            .map(|version| &version.$field),
+                    ) {
+                        conflicts.push(format!(
+                            "    LAND [{}, {}]: {} changed by several plugins, the last change was kept",
+                            last.grid.0, last.grid.1, $subrecord
+                        ));
+                    }
+                };
+            }
+            merge_subrecord!(
+                vertex_heights,
+                "vertex heights",
+                Some(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS)
+            );
+            merge_subrecord!(
+                vertex_normals,
+                "vertex normals",
+                Some(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS)
+            );
+            merge_subrecord!(
+                vertex_colors,
+                "vertex colors",
+                Some(LandscapeFlags::USES_VERTEX_COLORS)
+            );
+            merge_subrecord!(
+                texture_indices,
+                "texture indices",
+                Some(LandscapeFlags::USES_TEXTURES)
+            );
+            merge_subrecord!(world_map_data, "world map data", None);
+            merged_land.landscape_flags =
+                versions.iter().fold(base.landscape_flags, |acc, version| {
+                    acc | version.landscape_flags
+                });
+            *last = merged_land;
+            if !h.g.list_options.debug {
+                prevs.clear();
+            }
+            merged = increment!(merged);
+        }
+    }
+    if merged > 0 {
+        let text = format!("Output plugin {name:?}: {merged} landscape records merged");
+        msg(text, 1, cfg, log)?;
+    }
+    show_conflicts(&conflicts, "landscape records", name, 1, cfg, log)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::merge;
+    use crate::{get_test_cfg_and_log, Helper, Out, WarningKind};
+    use tes3::esp::{Landscape, LandscapeFlags};
+
+    // COMMENT: texture 0 stands for version without texture indices
+    fn get_land(texture: u16) -> Landscape {
+        let mut land = Landscape {
+            grid: (1, -2),
+            ..Default::default()
+        };
+        if texture != 0 {
+            land.landscape_flags.insert(LandscapeFlags::USES_TEXTURES);
+            if let Some(id) = land
+                .texture_indices
+                .data
+                .get_mut(0)
+                .and_then(|line| line.get_mut(0))
+            {
+                *id = texture;
+            }
+        }
+        land
+    }
+
+    fn get_texture(land: &Landscape) -> Option<u16> {
+        land.texture_indices
+            .data
+            .first()
+            .and_then(|line| line.first())
+            .copied()
+    }
+
+    fn run_merge(textures: &[u16]) -> (Landscape, Vec<WarningKind>) {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let prevs = textures
+            .iter()
+            .map(|&texture| get_land(texture))
+            .collect::<Vec<_>>();
+        let last = prevs
+            .last()
+            .cloned()
+            .expect("at least one version is required");
+        let mut out = Out::default();
+        out.land.push((last, prevs));
+        merge("Test.esp", &mut out, &Helper::new(), &cfg, &mut log).expect("failed to merge");
+        let (merged, _) = out.land.pop().expect("merged record is missing");
+        let kinds = log
+            .warnings
+            .unwrap_or_default()
+            .into_iter()
+            .map(|warning| warning.kind)
+            .collect();
+        (merged, kinds)
+    }
+
+    #[test]
+    fn change_of_earlier_version_survives_unchanged_last() {
+        let (merged, kinds) = run_merge(&[1, 2, 1]);
+        assert_eq!(
+            get_texture(&merged),
+            Some(2),
+            "change of the earlier version was reverted by the unchanged last version"
+        );
+        assert!(kinds.is_empty(), "single change was reported as a conflict");
+    }
+
+    #[test]
+    fn conflicting_changes_keep_the_last_one() {
+        let (merged, kinds) = run_merge(&[1, 2, 3]);
+        assert_eq!(
+            get_texture(&merged),
+            Some(3),
+            "the last change should be kept"
+        );
+        assert_eq!(
+            kinds,
+            [WarningKind::MergeConflicts],
+            "conflict wasn't reported"
+        );
+    }
+
+    #[test]
+    fn equal_changes_are_not_conflicts() {
+        let (merged, kinds) = run_merge(&[1, 2, 2]);
+        assert_eq!(
+            get_texture(&merged),
+            Some(2),
+            "equal changes should be kept"
+        );
+        assert!(
+            kinds.is_empty(),
+            "equal changes were reported as a conflict"
+        );
+    }
+
+    #[test]
+    fn missing_subrecord_is_not_a_change() {
+        let (merged, kinds) = run_merge(&[1, 2, 0]);
+        assert_eq!(
+            get_texture(&merged),
+            Some(2),
+            "version without texture indices reverted the change"
+        );
+        assert!(
+            kinds.is_empty(),
+            "version without texture indices was reported as a conflict"
+        );
+        assert!(
+            merged
+                .landscape_flags
+                .contains(LandscapeFlags::USES_TEXTURES),
+            "flag of the merged subrecord was lost"
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use super::{is_merge_skipped, last_changed};
+use crate::{increment, msg, Cfg, Helper, Log, Out};
+use anyhow::{Context as _, Result};
+use hashbrown::{hash_map::Entry, HashMap};
+
+type LevEntry = (String, u16);
+type LevKey = (String, u16);
+
+pub fn merge(name: &str, out: &mut Out, h: &Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut merged: usize = 0;
+    macro_rules! merge_lev {
+        ($type:ident, $list:ident, $flags:ident) => {
+            for &mut (ref mut last, ref mut prevs) in &mut out.$type {
+                if is_merge_skipped(stringify!($type), &last.id, h, cfg) {
+                    continue;
+                }
+                if let Some((base, versions)) = prevs.split_first() {
+                    last.$list = merge_entries(&base.$list, versions.iter().map(|v| &v.$list));
+                    last.chance_none =
+                        last_changed(base.chance_none, versions.iter().map(|v| v.chance_none));
+                    last.$flags = last_changed(base.$flags, versions.iter().map(|v| v.$flags));
+                    if !h.g.list_options.debug {
+                        prevs.clear();
+                    }
+                    merged = increment!(merged);
+                }
+            }
+        };
+    }
+    merge_lev!(levi, items, leveled_item_flags);
+    merge_lev!(levc, creatures, leveled_creature_flags);
+    if merged > 0 {
+        let text = format!("Output plugin {name:?}: {merged} leveled lists merged");
+        msg(text, 1, cfg, log)?;
+    }
+    Ok(())
+}
+
+fn merge_entries<'a, I: Iterator<Item = &'a Vec<LevEntry>>>(
+    base: &[LevEntry],
+    versions: I,
+) -> Vec<LevEntry> {
+    let base_counts = count_entries(base);
+    let mut removed_counts: HashMap<LevKey, usize> = HashMap::new();
+    let mut added: Vec<&LevEntry> = Vec::new();
+    let mut added_counts: HashMap<LevKey, usize> = HashMap::new();
+    for version in versions {
+        let version_counts = count_entries(version);
+        for (key, &base_count) in &base_counts {
+            let count = base_count.saturating_sub(version_counts.get(key).copied().unwrap_or(0));
+            if count > 0 {
+                removed_counts
+                    .entry(key.clone())
+                    .and_modify(|removed| *removed = (*removed).max(count))
+                    .or_insert(count);
+            }
+        }
+        for entry in version {
+            let key = get_key(entry);
+            let count = version_counts
+                .get(&key)
+                .copied()
+                .unwrap_or(1)
+                .saturating_sub(base_counts.get(&key).copied().unwrap_or(0));
+            if count == 0 {
+                continue;
+            }
+            match added_counts.entry(key) {
+                Entry::Vacant(v) => {
+                    v.insert(count);
+                    added.push(entry);
+                }
+                Entry::Occupied(mut o) => {
+                    if *o.get() < count {
+                        o.insert(count);
+                    }
+                }
+            }
+        }
+    }
+    // COMMENT: copies of base entries are removed in the same way as added ones are counted, i.e. the largest number of copies removed by any version wins
+    let mut kept_counts: HashMap<LevKey, usize> = base_counts
+        .into_iter()
+        .map(|(key, count)| {
+            let removed = removed_counts.get(&key).copied().unwrap_or(0);
+            (key, count.saturating_sub(removed))
+        })
+        .collect();
+    let mut result: Vec<LevEntry> = base
+        .iter()
+        .filter(|entry| {
+            kept_counts
+                .get_mut(&get_key(entry))
+                .is_some_and(|count| match count.checked_sub(1) {
+                    Some(left) => {
+                        *count = left;
+                        true
+                    }
+                    None => false,
+                })
+        })
+        .cloned()
+        .collect();
+    for entry in added {
+        let count = added_counts.get(&get_key(entry)).copied().unwrap_or(1);
+        result.extend((0..count).map(|_| entry.clone()));
+    }
+    result.sort_by_key(|&(_, level)| level);
+    result
+}
+
+fn count_entries(list: &[LevEntry]) -> HashMap<LevKey, usize> {
+    let mut counts = HashMap::new();
+    for entry in list {
+        counts
+            .entry(get_key(entry))
+            .and_modify(|count: &mut usize| *count = count.saturating_add(1))
+            .or_insert(1);
+    }
+    counts
+}
+
+fn get_key(&(ref id, level): &LevEntry) -> LevKey {
+    (id.to_lowercase(), level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_entries, LevEntry};
+
+    fn entries(list: &[(&str, u16)]) -> Vec<LevEntry> {
+        list.iter()
+            .map(|&(id, level)| (id.to_owned(), level))
+            .collect()
+    }
+
+    #[test]
+    fn additions_of_all_versions_are_kept() {
+        let base = entries(&[("a", 1), ("b", 5)]);
+        let version_1 = entries(&[("a", 1), ("b", 5), ("c", 3)]);
+        let version_2 = entries(&[("a", 1), ("b", 5), ("d", 7)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1, &version_2].into_iter()),
+            entries(&[("a", 1), ("c", 3), ("b", 5), ("d", 7)]),
+            "additions of every version should be merged and sorted by level"
+        );
+    }
+
+    #[test]
+    fn removal_by_any_version_wins() {
+        let base = entries(&[("a", 1), ("b", 5)]);
+        let version_1 = entries(&[("b", 5)]);
+        let version_2 = entries(&[("a", 1), ("b", 5), ("c", 3)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1, &version_2].into_iter()),
+            entries(&[("c", 3), ("b", 5)]),
+            "entry removed by one version should stay removed"
+        );
+    }
+
+    #[test]
+    fn same_level_is_part_of_the_key() {
+        let base = entries(&[("a", 1)]);
+        let version_1 = entries(&[("a", 2)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1].into_iter()),
+            entries(&[("a", 2)]),
+            "entry with the same id and another level should replace the base one"
+        );
+    }
+
+    #[test]
+    fn duplicate_additions_are_not_summed() {
+        let base = entries(&[("a", 1)]);
+        let version_1 = entries(&[("a", 1), ("c", 3), ("c", 3)]);
+        let version_2 = entries(&[("a", 1), ("C", 3)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1, &version_2].into_iter()),
+            entries(&[("a", 1), ("c", 3), ("c", 3)]),
+            "the largest count of the added entry should be used"
+        );
+    }
+
+    #[test]
+    fn ids_are_compared_case_insensitively() {
+        let base = entries(&[("Iron Sword", 1)]);
+        let version_1 = entries(&[("iron sword", 1)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1].into_iter()),
+            entries(&[("Iron Sword", 1)]),
+            "entry with id in another case should be treated as the same entry"
+        );
+    }
+
+    #[test]
+    fn lowered_count_of_duplicated_entry_removes_copies() {
+        let base = entries(&[("a", 1), ("a", 1), ("b", 5)]);
+        let version_1 = entries(&[("a", 1), ("b", 5)]);
+        let version_2 = entries(&[("a", 1), ("a", 1), ("b", 5), ("c", 3)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1, &version_2].into_iter()),
+            entries(&[("a", 1), ("c", 3), ("b", 5)]),
+            "copy removed by one version should stay removed"
+        );
+    }
+
+    #[test]
+    fn raised_count_of_base_entry_adds_copies() {
+        let base = entries(&[("a", 1), ("b", 5)]);
+        let version_1 = entries(&[("a", 1), ("a", 1), ("b", 5)]);
+        let version_2 = entries(&[("a", 1), ("b", 5)]);
+        assert_eq!(
+            merge_entries(&base, [&version_1, &version_2].into_iter()),
+            entries(&[("a", 1), ("a", 1), ("b", 5)]),
+            "copy added by one version should be kept"
+        );
+    }
+}
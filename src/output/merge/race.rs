@@ -0,0 +1,146 @@
+use super::{is_merge_skipped, last_changed};
+use crate::{increment, msg, Cfg, Helper, Log, Out};
+use anyhow::{Context as _, Result};
+
+pub fn merge(name: &str, out: &mut Out, h: &Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut merged: usize = 0;
+    macro_rules! merge_spells_record {
+        ($type:ident, [$($field:ident),+], [$($data:ident),*], [$($each:ident),*]) => {
+            for &mut (ref mut last, ref mut prevs) in &mut out.$type {
+                if is_merge_skipped(stringify!($type), &last.id, h, cfg) {
+                    continue;
+                }
+                if let Some((base, versions)) = prevs.split_first() {
+                    let mut merged_record = base.clone();
+                    merged_record.id.clone_from(&last.id);
+                    merged_record.spells =
+                        merge_spells(&base.spells, versions.iter().map(|v| &v.spells));
+                    $(merged_record.$field.clone_from(last_changed(
+                        &base.$field,
+                        versions.iter().map(|v| &v.$field),
+                    ));)+
+                    $(merged_record.data.$data.clone_from(last_changed(
+                        &base.data.$data,
+                        versions.iter().map(|v| &v.data.$data),
+                    ));)*
+                    $(merge_each(
+                        &mut merged_record.data.$each,
+                        &base.data.$each,
+                        &versions.iter().map(|v| v.data.$each.as_slice()).collect::<Vec<_>>(),
+                    );)*
+                    *last = merged_record;
+                    if !h.g.list_options.debug {
+                        prevs.clear();
+                    }
+                    merged = increment!(merged);
+                }
+            }
+        };
+    }
+    merge_spells_record!(
+        race,
+        [flags, name, description],
+        [height, weight, flags],
+        [skill_bonuses, attributes]
+    );
+    merge_spells_record!(bsgn, [flags, name, texture, description], [], []);
+    if merged > 0 {
+        let text = format!("Output plugin {name:?}: {merged} races and birthsigns merged");
+        msg(text, 1, cfg, log)?;
+    }
+    Ok(())
+}
+
+// COMMENT: spell lists are united, base order is kept and spells added by later versions are appended
+fn merge_spells<'a, I: Iterator<Item = &'a Vec<String>>>(
+    base: &[String],
+    versions: I,
+) -> Vec<String> {
+    let mut merged: Vec<String> = base.to_vec();
+    for version in versions {
+        for spell in version {
+            if !contains(&merged, spell) {
+                merged.push(spell.clone());
+            }
+        }
+    }
+    merged
+}
+
+// COMMENT: each entry of the list, e.g. skill bonus or attribute, is taken from the last version that changed it
+fn merge_each<T: PartialEq + Clone>(merged: &mut [T], base: &[T], versions: &[&[T]]) {
+    for (index, (merged_entry, base_entry)) in merged.iter_mut().zip(base).enumerate() {
+        merged_entry.clone_from(last_changed(
+            base_entry,
+            versions.iter().filter_map(|version| version.get(index)),
+        ));
+    }
+}
+
+fn contains<S: AsRef<str>>(list: &[S], spell: &str) -> bool {
+    list.iter()
+        .any(|item| item.as_ref().eq_ignore_ascii_case(spell))
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{merge, merge_each, merge_spells};
+    use crate::{get_test_cfg_and_log, Helper, Out};
+    use tes3::esp::Race;
+
+    fn spells(list: &[&str]) -> Vec<String> {
+        list.iter().map(|&spell| spell.to_owned()).collect()
+    }
+
+    #[test]
+    fn spells_are_united_in_base_order() {
+        let base = spells(&["a", "b"]);
+        let version_1 = spells(&["b", "c"]);
+        let version_2 = spells(&["A", "d"]);
+        assert_eq!(
+            merge_spells(&base, [&version_1, &version_2].into_iter()),
+            ["a", "b", "c", "d"],
+            "spells of all versions should be united without duplicates"
+        );
+    }
+
+    #[test]
+    fn entries_are_merged_one_by_one() {
+        let mut merged = [1, 2, 3];
+        let versions: [&[i32]; 3] = [&[5, 2, 3], &[1, 6, 3], &[1, 2]];
+        merge_each(&mut merged, &[1, 2, 3], &versions);
+        assert_eq!(
+            merged,
+            [5, 6, 3],
+            "changes of different entries by different versions should be combined"
+        );
+    }
+
+    #[test]
+    fn race_fields_and_spells_are_merged() {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let get_race = |name: &str, race_spells: &[&str]| Race {
+            id: "test_race".to_owned(),
+            name: name.to_owned(),
+            spells: spells(race_spells),
+            ..Default::default()
+        };
+        let version_1 = get_race("Renamed", &["a", "b"]);
+        let version_2 = get_race("Base", &["c"]);
+        let mut out = Out::default();
+        out.race.push((
+            version_2.clone(),
+            vec![get_race("Base", &["a"]), version_1, version_2],
+        ));
+        merge("Test.esp", &mut out, &Helper::new(), &cfg, &mut log).expect("failed to merge");
+        let &(ref merged, ref prevs) = out.race.first().expect("merged record is missing");
+        assert_eq!(merged.name, "Renamed", "name change was lost");
+        assert_eq!(
+            merged.spells,
+            ["a", "b", "c"],
+            "spells omitted by the last version were dropped"
+        );
+        assert!(prevs.is_empty(), "history wasn't cleared after merging");
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{show_record_notes, Cfg, Helper, Log, Out, WarningKind};
+use anyhow::{Context as _, Result};
+
+const CELL_SIZE: i32 = 8192;
+
+// COMMENT: statics may come both from merged plugins and from masters loaded while processing plugins
+#[allow(clippy::float_arithmetic)]
+pub fn check_pgrd_statics(
+    name: &str,
+    out: &Out,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let distance = f64::from(cfg.advanced.pathgrid_static_distance);
+    let mut points_near_statics: Vec<String> = Vec::new();
+    for &(ref pgrd, _) in &out.pgrd {
+        let (cell_meta, offset) = match h.g.r.int_cells.get(&pgrd.cell.to_lowercase()) {
+            Some(cell_meta) => (cell_meta, (0, 0)),
+            None => match h.g.r.ext_cells.get(&pgrd.data.grid) {
+                Some(cell_meta) => (
+                    cell_meta,
+                    (
+                        pgrd.data.grid.0.saturating_mul(CELL_SIZE),
+                        pgrd.data.grid.1.saturating_mul(CELL_SIZE),
+                    ),
+                ),
+                None => continue,
+            },
+        };
+        let cell = &out
+            .cell
+            .get(cell_meta.global_cell_id)
+            .with_context(|| {
+                format!(
+                    "Bug: indexing slicing out.cell[{}]",
+                    cell_meta.global_cell_id
+                )
+            })?
+            .0;
+        for reference in cell.references.values() {
+            if reference.mast_index != 0 {
+                continue;
+            }
+            let id_low = reference.id.to_lowercase();
+            if !h.g.r.stat.contains_key(&id_low) && !h.g.master_records.stat.contains_key(&id_low) {
+                continue;
+            }
+            for (index, point) in pgrd.points.iter().enumerate() {
+                let [x, y, z] = point.location;
+                let location = [x.saturating_add(offset.0), y.saturating_add(offset.1), z];
+                let squared_distance: f64 = location
+                    .iter()
+                    .zip(reference.translation.iter())
+                    .map(|(&a, &b)| (f64::from(a) - f64::from(b)).powi(2))
+                    .sum();
+                if squared_distance <= distance.powi(2) {
+                    points_near_statics.push(format!(
+                        "    PGRD \"{}\": point {index} {:?} is within {} units of static \"{}\"",
+                        pgrd.cell, point.location, distance, reference.id
+                    ));
+                }
+            }
+        }
+    }
+    let points_near_statics_len = points_near_statics.len();
+    let text = format!(
+        "  {} pathgrid point{} found next to newly added statics in \"{}\"",
+        points_near_statics_len,
+        if points_near_statics_len == 1 {
+            " was"
+        } else {
+            "s were"
+        },
+        name
+    );
+    show_record_notes(
+        &points_near_statics,
+        text,
+        WarningKind::PathgridPointsNearStatics,
+        1,
+        cfg,
+        log,
+    )
+}
@@ -0,0 +1,158 @@
+use crate::{
+    get_provenance_cell_name, msg, references_sorted, Cfg, Helper, Log, ProvenanceCell,
+    ProvenanceFile, ProvenancePlugin, ProvenanceRecord, ProvenanceRef, RefrId,
+};
+use anyhow::{Context as _, Result};
+use fs_err::write;
+use hashbrown::HashMap;
+use tes3::esp::{Plugin, Reference, TES3Object};
+
+pub const PROVENANCE_VERSION: u32 = 1;
+const UNKNOWN_PLUGIN: &str = "unknown";
+
+pub fn write_provenance(
+    name: &str,
+    plugin: &Plugin,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let provenance_name = format!("{name}{}", cfg.guts.provenance_suffix);
+    let plugins: Vec<ProvenancePlugin> =
+        h.g.plugins_processed
+            .iter()
+            .map(|plugin_info| ProvenancePlugin {
+                name: plugin_info.name.clone(),
+                masters: h
+                    .g
+                    .provenance
+                    .masters
+                    .get(&plugin_info.id)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
+            .collect();
+    let get_plugin_name = |plugin_id: usize| -> Result<String> {
+        h.g.plugins_processed
+            .get(plugin_id)
+            .map(|plugin_info| plugin_info.name_low.clone())
+            .with_context(|| format!("Bug: indexing slicing plugins_processed[{plugin_id}]"))
+    };
+    let mut records = Vec::with_capacity(h.g.provenance.order.len());
+    for key in &h.g.provenance.order {
+        let record =
+            h.g.provenance.records.get(key).with_context(|| {
+                format!("Bug: failed to find record {key:?} in provenance records")
+            })?;
+        records.push(ProvenanceRecord {
+            record_type: key.0.to_uppercase(),
+            id: key.1.clone(),
+            winner: get_plugin_name(record.winner)?,
+            overridden: record
+                .plugins
+                .iter()
+                .filter(|&&plugin_id| plugin_id != record.winner)
+                .map(|&plugin_id| get_plugin_name(plugin_id))
+                .collect::<Result<_>>()?,
+        });
+    }
+    let cells = get_provenance_cells(plugin, h);
+    let provenance_file = ProvenanceFile {
+        version: PROVENANCE_VERSION,
+        plugins,
+        records,
+        cells,
+    };
+    let text = if h.g.list_options.dry_run {
+        format!("Provenance file {provenance_name:?} would be written")
+    } else {
+        write(
+            &provenance_name,
+            toml::to_string(&provenance_file).with_context(|| {
+                format!("Failed to serialize provenance file {provenance_name:?}")
+            })?,
+        )
+        .with_context(|| format!("Failed to write provenance file {provenance_name:?}"))?;
+        format!("Provenance file {provenance_name:?} was written")
+    };
+    msg(text, 1, cfg, log)
+}
+
+fn get_provenance_cells(plugin: &Plugin, h: &Helper) -> Vec<ProvenanceCell> {
+    // COMMENT: reindexed references are tracked in reference map, otherwise they keep global indices stored in cell metas
+    let mut ref_sources: HashMap<RefrId, (&str, RefrId)> = HashMap::new();
+    if h.g.list_options.reindex || h.g.list_options.stable_ref_indices {
+        for &((ref plugin_name_low, _, local_refr), refr) in &h.g.ref_map.current {
+            ref_sources.insert(refr, (plugin_name_low.as_str(), local_refr));
+        }
+    } else {
+        for cell_meta in h.g.r.int_cells.values().chain(h.g.r.ext_cells.values()) {
+            for plugin_meta in &cell_meta.plugin_metas {
+                for plugin_refr in &plugin_meta.plugin_refrs {
+                    ref_sources.insert(
+                        plugin_refr.global_refr,
+                        (plugin_meta.plugin_name_low.as_str(), plugin_refr.local_refr),
+                    );
+                }
+            }
+        }
+    }
+    let mut masters: &[(String, u64)] = &[];
+    let mut cells = Vec::new();
+    for object in &plugin.objects {
+        #[allow(clippy::wildcard_enum_match_arm)]
+        match *object {
+            TES3Object::Header(ref header) => masters = &header.masters,
+            TES3Object::Cell(ref cell) => {
+                let mut references: Vec<&Reference> = cell.references.values().collect();
+                references_sorted(&mut references);
+                let refs = references
+                    .into_iter()
+                    .map(|reference| {
+                        let (plugin_name, original_refr_index) = if reference.mast_index == 0 {
+                            ref_sources
+                                .get(&reference.refr_index)
+                                .copied()
+                                .unwrap_or((UNKNOWN_PLUGIN, reference.refr_index))
+                        } else {
+                            (
+                                usize::try_from(reference.mast_index)
+                                    .ok()
+                                    .and_then(|mast_index| {
+                                        masters.get(mast_index.saturating_sub(1))
+                                    })
+                                    .map_or(UNKNOWN_PLUGIN, |&(ref master_name, _)| {
+                                        master_name.as_str()
+                                    }),
+                                reference.refr_index,
+                            )
+                        };
+                        let plugin_name_low = plugin_name.to_lowercase();
+                        let editor = if reference.mast_index == 0 {
+                            h.g.provenance
+                                .ref_editors
+                                .get(&(plugin_name_low.clone(), original_refr_index))
+                                .cloned()
+                        } else {
+                            None
+                        };
+                        ProvenanceRef {
+                            refr_index: reference.refr_index,
+                            mast_index: reference.mast_index,
+                            id: reference.id.clone(),
+                            plugin: plugin_name_low,
+                            original_refr_index,
+                            editor,
+                        }
+                    })
+                    .collect();
+                cells.push(ProvenanceCell {
+                    cell: get_provenance_cell_name(cell),
+                    refs,
+                });
+            }
+            _ => {}
+        }
+    }
+    cells
+}
@@ -0,0 +1,246 @@
+use crate::{increment, msg, Cfg, Helper, Log, RefMapFile, RefMapKey, RefMapPlugin, RefrId};
+use anyhow::{anyhow, Context as _, Result};
+use fs_err::{read_to_string, write};
+use hashbrown::{hash_map::Entry, HashMap, HashSet};
+use std::path::Path;
+use tes3::esp::{Plugin, TES3Object};
+
+const REF_MAP_VERSION: u32 = 1;
+
+pub fn get_ref_map_name(name: &str, cfg: &Cfg) -> String {
+    format!("{name}{}", cfg.guts.ref_map_suffix)
+}
+
+// COMMENT: previous output plugin is kept loaded to be compared with the new one by write_output_plugin
+pub fn seed_ref_map(
+    name: &str,
+    old_plugin: &mut Plugin,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    if !Path::new(name).exists() {
+        let text = format!("Output plugin {name:?}: references will be numbered from scratch because previous version of the plugin doesn't exist");
+        return msg(text, 1, cfg, log);
+    }
+    old_plugin
+        .load_path(name)
+        .with_context(|| format!("Failed to read previous output plugin {name:?}"))?;
+    let mut old_refrs: HashSet<RefrId> = HashSet::new();
+    for object in &old_plugin.objects {
+        if let TES3Object::Cell(ref cell) = *object {
+            old_refrs.extend(
+                cell.references
+                    .values()
+                    .filter(|reference| reference.mast_index == 0)
+                    .map(|reference| reference.refr_index),
+            );
+        }
+    }
+    let max_refr = old_refrs.iter().max().copied().unwrap_or_default();
+    h.g.ref_map.next_refr = increment!(max_refr);
+    let ref_map_name = get_ref_map_name(name, cfg);
+    if !Path::new(&ref_map_name).exists() {
+        let text = format!("Output plugin {name:?}: references will get new indices because reference map {ref_map_name:?} doesn't exist");
+        return msg(text, 0, cfg, log);
+    }
+    let ref_map_file: RefMapFile = toml::from_str(
+        &read_to_string(&ref_map_name)
+            .with_context(|| format!("Failed to read reference map {ref_map_name:?}"))?,
+    )
+    .with_context(|| format!("Failed to parse reference map {ref_map_name:?}"))?;
+    if ref_map_file.version != REF_MAP_VERSION {
+        return Err(anyhow!(
+            "Error: reference map {ref_map_name:?} has unsupported version \"{}\", expected version is \"{REF_MAP_VERSION}\"",
+            ref_map_file.version
+        ));
+    }
+    let mut dropped: usize = 0;
+    for plugin in ref_map_file.plugins {
+        for (mast_index, refr_index, refr) in plugin.refs {
+            if old_refrs.contains(&refr) {
+                h.g.ref_map
+                    .previous
+                    .insert((plugin.name.clone(), mast_index, refr_index), refr);
+            } else {
+                dropped = increment!(dropped);
+            }
+        }
+    }
+    h.g.ref_map.seeded = true;
+    if dropped != 0 {
+        let text = format!("Output plugin {name:?}: {dropped} entries of reference map {ref_map_name:?} were ignored because previous version of the plugin doesn't contain such references");
+        msg(text, 0, cfg, log)?;
+    }
+    Ok(())
+}
+
+pub fn write_ref_map(
+    name: &str,
+    plugin: &Plugin,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let ref_map_name = get_ref_map_name(name, cfg);
+    let not_reindexed;
+    let current = if h.g.list_options.reindex || h.g.list_options.stable_ref_indices {
+        &h.g.ref_map.current
+    } else {
+        not_reindexed = get_not_reindexed_refs(plugin, h);
+        &not_reindexed
+    };
+    let mut plugin_ids: HashMap<&str, usize> = HashMap::new();
+    let mut plugins: Vec<RefMapPlugin> = Vec::with_capacity(h.g.plugins_processed.len());
+    for plugin_info in &h.g.plugins_processed {
+        if let Entry::Vacant(v) = plugin_ids.entry(&plugin_info.name_low) {
+            v.insert(plugins.len());
+            plugins.push(RefMapPlugin {
+                name: plugin_info.name_low.clone(),
+                refs: Vec::new(),
+            });
+        }
+    }
+    for &((ref plugin_name_low, mast_index, refr_index), refr) in current {
+        let plugin_id = plugin_ids.get(plugin_name_low.as_str()).with_context(|| {
+            format!("Bug: failed to find plugin \"{plugin_name_low}\" in plugins_processed")
+        })?;
+        plugins
+            .get_mut(*plugin_id)
+            .with_context(|| format!("Bug: indexing slicing plugins[{plugin_id}]"))?
+            .refs
+            .push((mast_index, refr_index, refr));
+    }
+    plugins.retain(|plugin| !plugin.refs.is_empty());
+    for plugin in &mut plugins {
+        plugin.refs.sort_unstable();
+    }
+    let ref_map_file = RefMapFile {
+        version: REF_MAP_VERSION,
+        plugins,
+    };
+    let text = if h.g.list_options.dry_run {
+        format!("Reference map {ref_map_name:?} would be written")
+    } else {
+        write(
+            &ref_map_name,
+            toml::to_string(&ref_map_file)
+                .with_context(|| format!("Failed to serialize reference map {ref_map_name:?}"))?,
+        )
+        .with_context(|| format!("Failed to write reference map {ref_map_name:?}"))?;
+        format!("Reference map {ref_map_name:?} was written")
+    };
+    msg(text, 1, cfg, log)
+}
+
+// COMMENT: references that weren't reindexed keep indices given during merge, excluded ones are skipped
+fn get_not_reindexed_refs(plugin: &Plugin, h: &Helper) -> Vec<(RefMapKey, RefrId)> {
+    let mut output_refrs: HashSet<RefrId> = HashSet::new();
+    for object in &plugin.objects {
+        if let TES3Object::Cell(ref cell) = *object {
+            output_refrs.extend(
+                cell.references
+                    .values()
+                    .filter(|reference| reference.mast_index == 0)
+                    .map(|reference| reference.refr_index),
+            );
+        }
+    }
+    let mut refs = Vec::new();
+    for cell_meta in h.g.r.int_cells.values().chain(h.g.r.ext_cells.values()) {
+        for plugin_meta in &cell_meta.plugin_metas {
+            for plugin_refr in &plugin_meta.plugin_refrs {
+                if output_refrs.contains(&plugin_refr.global_refr) {
+                    refs.push((
+                        (
+                            plugin_meta.plugin_name_low.clone(),
+                            0,
+                            plugin_refr.local_refr,
+                        ),
+                        plugin_refr.global_refr,
+                    ));
+                }
+            }
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{get_ref_map_name, seed_ref_map, REF_MAP_VERSION};
+    use crate::{get_test_cfg_and_log, Helper, RefMapFile, RefMapPlugin};
+    use fs_err::{create_dir_all, remove_dir_all, write};
+    use hashbrown::HashMap;
+    use std::{env::temp_dir, process};
+    use tes3::esp::{Cell, Header, Plugin, Reference, TES3Object};
+
+    #[test]
+    fn only_references_of_previous_plugin_are_seeded() {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let dir = temp_dir().join(format!("habasi_ref_map_test_{}", process::id()));
+        create_dir_all(&dir).expect("failed to create test directory");
+        let name = dir.join("MergedPlugin.esp").to_string_lossy().into_owned();
+        let mut references = HashMap::new();
+        for refr_index in [3, 7] {
+            references.insert(
+                (0, refr_index),
+                Reference {
+                    refr_index,
+                    id: "test_static".to_owned(),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut old_plugin = Plugin::new();
+        old_plugin.objects = vec![
+            TES3Object::Header(Header::default()),
+            TES3Object::Cell(Cell {
+                references,
+                ..Default::default()
+            }),
+        ];
+        old_plugin
+            .save_path(&name)
+            .expect("failed to save previous output plugin");
+        let ref_map_file = RefMapFile {
+            version: REF_MAP_VERSION,
+            plugins: vec![RefMapPlugin {
+                name: "plugin.esp".to_owned(),
+                refs: vec![(0, 1, 3), (0, 2, 9)],
+            }],
+        };
+        write(
+            get_ref_map_name(&name, &cfg),
+            toml::to_string(&ref_map_file).expect("failed to serialize reference map"),
+        )
+        .expect("failed to write reference map");
+        let mut h = Helper::new();
+        let mut loaded_plugin = Plugin::new();
+        let result = seed_ref_map(&name, &mut loaded_plugin, &mut h, &cfg, &mut log);
+        remove_dir_all(&dir).expect("failed to remove test directory");
+        result.expect("failed to seed reference map");
+        assert_eq!(
+            h.g.ref_map
+                .previous
+                .get(&("plugin.esp".to_owned(), 0, 1))
+                .copied(),
+            Some(3),
+            "reference of the previous plugin wasn't seeded"
+        );
+        assert_eq!(
+            h.g.ref_map.previous.len(),
+            1,
+            "reference missing from the previous plugin was seeded"
+        );
+        assert_eq!(
+            h.g.ref_map.next_refr, 8,
+            "new references should be numbered above the previous ones"
+        );
+        assert!(
+            !loaded_plugin.objects.is_empty(),
+            "previous output plugin should be kept loaded"
+        );
+    }
+}
@@ -1,11 +1,18 @@
+use super::{check_pgrd_statics, merge, process_duplicate_refs};
 use crate::{
-    get_cell_name, increment, msg, references_sorted, show_removed_record_ids, CellExtGrid, Cfg,
-    Helper, Log, Mode, OldRefSources, Out, RefSources, StatsUpdateKind,
+    clean_itm, get_cell_name, increment, msg, references_sorted, show_record_notes,
+    show_removed_record_ids, CellExtGrid, Cfg, Helper, Log, Mode, OldRefSources, Out, RefSources,
+    RefrId, StatsUpdateKind, WarningKind, LAND_SIDE,
 };
 use anyhow::{anyhow, Context as _, Result};
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 use rayon::iter::{IntoParallelRefMutIterator as _, ParallelIterator as _};
-use tes3::esp::{Cell, CellFlags, ObjectFlags, Reference, Static};
+use tes3::esp::{
+    Cell, CellFlags, GameSetting, LandscapeFlags, ObjectFlags, Reference, Static, VertexHeights,
+};
+
+type LandHeights = [[f32; LAND_SIDE]; LAND_SIDE];
+type LandVertex = (usize, usize);
 
 pub fn transform(
     name: &str,
@@ -17,13 +24,27 @@ pub fn transform(
     resort_skil_mgef(&mut out);
     set_creature_scale_to_none_if_default(&mut out);
     remove_ambi_whgt_from_deleted_cells(&mut out);
+    merge(name, &mut out, h, cfg, log)?;
+    if h.g.list_options.check_land_seams {
+        process_land_seams(name, &mut out, h, cfg, log)?;
+    }
+    if h.g.list_options.merge_pathgrids && cfg.advanced.pathgrid_static_distance > 0 {
+        check_pgrd_statics(name, &out, h, cfg, log)?;
+    }
+    if h.g.list_options.check_duplicate_refs {
+        process_duplicate_refs(name, &mut out, h, cfg, log)?;
+    }
+    if h.g.list_options.clean_itm {
+        clean_itm(name, &mut out, h, cfg, log)?;
+    }
+    out.gmst = process_evil_gmsts(out.gmst, name, h, cfg, log)?;
     if matches!(h.g.list_options.mode, Mode::Grass) {
         out.stat = exclude_non_grass_statics(out.stat, name, h, cfg, log)?;
         out.cell = exclude_interior_and_empty_cells(out.cell, name, h, cfg, log)?;
     }
     exclude_infos(&mut out, name, cfg, log)?;
     exclude_deleted_refs_mast_id_0(&mut out);
-    if h.g.list_options.reindex {
+    if h.g.list_options.reindex || h.g.list_options.stable_ref_indices {
         reindex_references(name, &mut out, h, cfg, log)?;
     }
     Ok(out)
@@ -62,6 +83,60 @@ fn exclude_non_grass_statics(
     Ok(stats)
 }
 
+fn process_evil_gmsts(
+    src: Vec<(GameSetting, Vec<GameSetting>)>,
+    name: &str,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<Vec<(GameSetting, Vec<GameSetting>)>> {
+    let exclude = h.g.list_options.exclude_evil_gmsts;
+    let mut gmsts: Vec<(GameSetting, Vec<GameSetting>)> = Vec::with_capacity(src.len());
+    let mut evil_gmsts = Vec::new();
+    for gmst in src {
+        let id_low = gmst.0.id.to_lowercase();
+        // COMMENT: game settings with record rules are intended
+        if cfg.advanced.evil_gmsts.get(&id_low) == Some(&gmst.0.value)
+            && cfg.advanced.record_rules.get("gmst", &id_low).is_none()
+        {
+            if exclude {
+                evil_gmsts.push(format!(
+                    "    Record GMST: \"{}\" was excluded from the result because it has vanilla default value",
+                    &gmst.0.id
+                ));
+                h.g.stats.gmst(StatsUpdateKind::Excluded);
+                continue;
+            }
+            evil_gmsts.push(format!(
+                "    Record GMST: \"{}\" has vanilla default value {:?}",
+                &gmst.0.id, &gmst.0.value
+            ));
+        }
+        gmsts.push(gmst);
+    }
+    if exclude {
+        show_removed_record_ids(
+            &evil_gmsts,
+            "\"exclude_evil_gmsts\" and vanilla default value of GMST",
+            name,
+            1,
+            cfg,
+            log,
+        )?;
+    } else {
+        let evil_gmsts_len = evil_gmsts.len();
+        let text = format!(
+            "  {} GMST record{} with vanilla default value{} found in \"{}\", such records revert changes of other plugins",
+            evil_gmsts_len,
+            if evil_gmsts_len == 1 { " was" } else { "s were" },
+            if evil_gmsts_len == 1 { "" } else { "s" },
+            name
+        );
+        show_record_notes(&evil_gmsts, text, WarningKind::EvilGmsts, 1, cfg, log)?;
+    }
+    Ok(gmsts)
+}
+
 fn exclude_interior_and_empty_cells(
     src: Vec<(Cell, Vec<Cell>)>,
     name: &str,
@@ -139,6 +214,20 @@ fn reindex_references(
         HashMap::new();
     let mut new_ext_ref_sources: HashMap<CellExtGrid, (RefSources, OldRefSources)> = HashMap::new();
     let mut refr = 1_u32;
+    let stable = h.g.list_options.stable_ref_indices;
+    let mut ref_map_keys: HashMap<RefrId, (&str, RefrId)> = HashMap::new();
+    if stable || h.g.list_options.write_provenance || h.g.list_options.write_ref_map {
+        for cell_meta in h.g.r.int_cells.values().chain(h.g.r.ext_cells.values()) {
+            for plugin_meta in &cell_meta.plugin_metas {
+                for plugin_refr in &plugin_meta.plugin_refrs {
+                    ref_map_keys.insert(
+                        plugin_refr.global_refr,
+                        (plugin_meta.plugin_name_low.as_str(), plugin_refr.local_refr),
+                    );
+                }
+            }
+        }
+    }
     for &mut (ref mut last, _) in &mut out.cell {
         let mut reindexed_ext_ref_sources: RefSources = HashMap::new();
         let mut reindexed_ext_old_ref_sources: OldRefSources = HashMap::new();
@@ -172,19 +261,32 @@ fn reindex_references(
         references_sorted(&mut references);
         for reference in references {
             if reference.mast_index == 0 {
+                let ref_map_key = ref_map_keys.get(&reference.refr_index).map(
+                    |&(plugin_name_low, local_refr)| (plugin_name_low.to_owned(), 0, local_refr),
+                );
+                let new_refr = if stable {
+                    h.g.ref_map.get_refr(ref_map_key)?
+                } else {
+                    let current_refr = refr;
+                    refr = increment!(refr);
+                    if let Some(key) = ref_map_key {
+                        h.g.ref_map.current.push((key, current_refr));
+                    }
+                    current_refr
+                };
                 let new_ref = Reference {
-                    refr_index: refr,
+                    refr_index: new_refr,
                     ..reference.clone()
                 };
-                new_refs.insert((0_u32, refr), new_ref);
+                new_refs.insert((0_u32, new_refr), new_ref);
                 if is_ext_ref {
                     match ext_ref_sources.0.get(&(reference.mast_index, reference.refr_index)) {
                         Some(v) => {
-                            reindexed_ext_ref_sources.insert((0, refr), *v);
+                            reindexed_ext_ref_sources.insert((0, new_refr), *v);
                         }
                         None => match ext_ref_sources.1.get(&(reference.mast_index, reference.refr_index)) {
                             Some(y) => {
-                                reindexed_ext_old_ref_sources.insert((0, refr), y.clone());
+                                reindexed_ext_old_ref_sources.insert((0, new_refr), y.clone());
                             }
                             None => {
                                 return Err(anyhow!(
@@ -196,7 +298,6 @@ fn reindex_references(
                         },
                     }
                 }
-                refr = increment!(refr);
             } else {
                 new_refs.insert(
                     (reference.mast_index, reference.refr_index),
@@ -232,7 +333,14 @@ fn reindex_references(
         }
     }
     h.g.r.ext_ref_sources = new_ext_ref_sources;
-    let text = format!("Output plugin {name:?}: references reindexed");
+    let text = if stable {
+        format!(
+            "Output plugin {name:?}: references reindexed, {} of them kept previous indices",
+            h.g.ref_map.kept
+        )
+    } else {
+        format!("Output plugin {name:?}: references reindexed")
+    };
     msg(text, 1, cfg, log)?;
     Ok(())
 }
@@ -244,6 +352,7 @@ fn exclude_infos(out: &mut Out, name: &str, cfg: &Cfg, log: &mut Log) -> Result<
             dial.excluded_infos.sort_unstable();
             for n in dial.excluded_infos.iter().rev() {
                 let info = dial.info.remove(*n);
+                dial.info_plugin_ids.remove(*n);
                 match cfg.advanced.keep_only_last_info_ids.get(&info.id) {
                         None => {
                             return Err(anyhow!(
@@ -309,3 +418,397 @@ fn remove_ambi_whgt_from_deleted_cells(out: &mut Out) {
         }
     }
 }
+
+#[allow(clippy::float_arithmetic, clippy::too_many_lines)]
+fn process_land_seams(
+    name: &str,
+    out: &mut Out,
+    h: &Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let stitch = h.g.list_options.stitch_land_seams;
+    let mut lands: HashMap<CellExtGrid, usize> = HashMap::new();
+    let mut heights: HashMap<CellExtGrid, LandHeights> = HashMap::new();
+    for (global_id, &(ref land, _)) in out.land.iter().enumerate() {
+        if land
+            .landscape_flags
+            .contains(LandscapeFlags::USES_VERTEX_HEIGHTS_AND_NORMALS)
+        {
+            lands.insert(land.grid, global_id);
+            heights.insert(land.grid, decode_heights(&land.vertex_heights));
+        }
+    }
+    let mut grids: Vec<CellExtGrid> = lands.keys().copied().collect();
+    grids.sort_unstable();
+    let mut seams = Vec::new();
+    let mut stitched: HashSet<CellExtGrid> = HashSet::new();
+    // COMMENT: normals are edited in buffer and written together with heights, because heights of some lands may fail to be stitched
+    let mut normals: HashMap<(CellExtGrid, LandVertex), [i8; 3]> = HashMap::new();
+    for &grid in &grids {
+        for east in [true, false] {
+            let neighbour = if east {
+                (grid.0.saturating_add(1), grid.1)
+            } else {
+                (grid.0, grid.1.saturating_add(1))
+            };
+            let (Some(&global_id), Some(&neighbour_global_id)) =
+                (lands.get(&grid), lands.get(&neighbour))
+            else {
+                continue;
+            };
+            let mut height_diffs: usize = 0;
+            let mut normal_diffs: usize = 0;
+            for index in 0..LAND_SIDE {
+                let (vertex, neighbour_vertex) = get_edge_vertices(east, index);
+                let height = get_height(&heights, grid, vertex)?;
+                let neighbour_height = get_height(&heights, neighbour, neighbour_vertex)?;
+                let normal = get_normal(out, &normals, (grid, global_id), vertex)?;
+                let neighbour_normal = get_normal(
+                    out,
+                    &normals,
+                    (neighbour, neighbour_global_id),
+                    neighbour_vertex,
+                )?;
+                #[allow(clippy::float_cmp)]
+                if height != neighbour_height {
+                    height_diffs = increment!(height_diffs);
+                    if stitch {
+                        let average = ((height + neighbour_height) / 2.0).round();
+                        set_height(&mut heights, grid, vertex, average)?;
+                        set_height(&mut heights, neighbour, neighbour_vertex, average)?;
+                    }
+                }
+                if normal != neighbour_normal {
+                    normal_diffs = increment!(normal_diffs);
+                    if stitch {
+                        let average = average_normal(&[normal, neighbour_normal]);
+                        normals.insert((grid, vertex), average);
+                        normals.insert((neighbour, neighbour_vertex), average);
+                    }
+                }
+            }
+            if height_diffs > 0 || normal_diffs > 0 {
+                seams.push((grid, neighbour, height_diffs, normal_diffs));
+                if stitch {
+                    stitched.insert(grid);
+                    stitched.insert(neighbour);
+                }
+            }
+        }
+    }
+    let corner_links = if stitch {
+        stitch_land_corners(
+            out,
+            &lands,
+            &grids,
+            &mut heights,
+            &mut normals,
+            &mut stitched,
+        )?
+    } else {
+        Vec::new()
+    };
+    let mut encoded = HashMap::with_capacity(stitched.len());
+    let mut not_stitched: HashSet<CellExtGrid> = HashSet::new();
+    for grid in &stitched {
+        let (Some(&global_id), Some(land_heights)) = (lands.get(grid), heights.get(grid)) else {
+            return Err(anyhow!(
+                "Bug: failed to find LAND {grid:?} while stitching land seams"
+            ));
+        };
+        let mut vertex_heights = out
+            .land
+            .get(global_id)
+            .with_context(|| format!("Bug: indexing slicing out.land[{global_id}]"))?
+            .0
+            .vertex_heights
+            .clone();
+        if encode_heights(land_heights, &mut vertex_heights) {
+            encoded.insert(*grid, (global_id, vertex_heights));
+        } else {
+            not_stitched.insert(*grid);
+        }
+    }
+    // COMMENT: seam is stitched only when both sides are encoded, otherwise neighbours would get averaged edge alone
+    let mut is_not_stitched_changed = !not_stitched.is_empty();
+    while is_not_stitched_changed {
+        is_not_stitched_changed = false;
+        for (grid, neighbour) in seams
+            .iter()
+            .map(|&(grid, neighbour, _, _)| (grid, neighbour))
+            .chain(corner_links.iter().copied())
+        {
+            if not_stitched.contains(&grid) != not_stitched.contains(&neighbour) {
+                not_stitched.insert(grid);
+                not_stitched.insert(neighbour);
+                is_not_stitched_changed = true;
+            }
+        }
+    }
+    for (grid, (global_id, vertex_heights)) in encoded {
+        if !not_stitched.contains(&grid) {
+            out.land
+                .get_mut(global_id)
+                .with_context(|| format!("Bug: indexing slicing out.land[{global_id}]"))?
+                .0
+                .vertex_heights = vertex_heights;
+        }
+    }
+    for ((grid, vertex), normal) in normals {
+        if !not_stitched.contains(&grid) {
+            let global_id = *lands.get(&grid).with_context(|| {
+                format!("Bug: failed to find LAND {grid:?} while stitching land seams")
+            })?;
+            set_normal(out, global_id, vertex, normal)?;
+        }
+    }
+    let mut seam_descriptions = Vec::with_capacity(seams.len());
+    for (grid, neighbour, height_diffs, normal_diffs) in seams {
+        seam_descriptions.push(format!(
+            "    LAND [{}, {}] - [{}, {}]: {height_diffs} edge vertices with different heights, {normal_diffs} with different normals{}",
+            grid.0,
+            grid.1,
+            neighbour.0,
+            neighbour.1,
+            if !stitch {
+                ""
+            } else if not_stitched.contains(&grid) || not_stitched.contains(&neighbour) {
+                ", not stitched due to too steep slope next to the edge"
+            } else {
+                ", stitched"
+            }
+        ));
+    }
+    show_land_seams(&seam_descriptions, stitch, name, 1, cfg, log)
+}
+
+// COMMENT: corner vertex is shared by up to four lands, while edges are stitched pairwise, so corners are averaged between all lands once more
+fn stitch_land_corners(
+    out: &Out,
+    lands: &HashMap<CellExtGrid, usize>,
+    grids: &[CellExtGrid],
+    heights: &mut HashMap<CellExtGrid, LandHeights>,
+    normals: &mut HashMap<(CellExtGrid, LandVertex), [i8; 3]>,
+    stitched: &mut HashSet<CellExtGrid>,
+) -> Result<Vec<(CellExtGrid, CellExtGrid)>> {
+    let mut corner_links = Vec::new();
+    let mut corners: Vec<CellExtGrid> = grids
+        .iter()
+        .flat_map(|&(x, y)| {
+            let west = x.saturating_sub(1);
+            let south = y.saturating_sub(1);
+            [(x, y), (west, y), (x, south), (west, south)]
+        })
+        .collect();
+    corners.sort_unstable();
+    corners.dedup();
+    for corner_origin in corners {
+        let mut corner = Vec::with_capacity(4);
+        for (corner_grid, vertex) in get_corner_vertices(corner_origin) {
+            if let Some(&global_id) = lands.get(&corner_grid) {
+                let height = get_height(heights, corner_grid, vertex)?;
+                let normal = get_normal(out, normals, (corner_grid, global_id), vertex)?;
+                corner.push((corner_grid, vertex, height, normal));
+            }
+        }
+        let Some(&(anchor, _, _, _)) = corner.first() else {
+            continue;
+        };
+        if corner.len() < 2 {
+            continue;
+        }
+        let corner_heights: Vec<f32> = corner.iter().map(|&(_, _, height, _)| height).collect();
+        let corner_normals: Vec<[i8; 3]> = corner.iter().map(|&(_, _, _, normal)| normal).collect();
+        let height = average_height(&corner_heights).round();
+        let normal = average_normal(&corner_normals);
+        #[allow(clippy::float_cmp)]
+        if corner.iter().any(|&(_, _, corner_height, corner_normal)| {
+            corner_height != height || corner_normal != normal
+        }) {
+            for &(corner_grid, vertex, _, _) in &corner {
+                set_height(heights, corner_grid, vertex, height)?;
+                normals.insert((corner_grid, vertex), normal);
+                stitched.insert(corner_grid);
+                corner_links.push((anchor, corner_grid));
+            }
+        }
+    }
+    Ok(corner_links)
+}
+
+fn get_edge_vertices(east: bool, index: usize) -> (LandVertex, LandVertex) {
+    let last = LAND_SIDE.saturating_sub(1);
+    if east {
+        ((index, last), (index, 0))
+    } else {
+        ((last, index), (0, index))
+    }
+}
+
+// COMMENT: north-east corner of the grid is shared with east, north and north-east neighbours
+fn get_corner_vertices(grid: CellExtGrid) -> [(CellExtGrid, LandVertex); 4] {
+    let last = LAND_SIDE.saturating_sub(1);
+    let east = grid.0.saturating_add(1);
+    let north = grid.1.saturating_add(1);
+    [
+        (grid, (last, last)),
+        ((east, grid.1), (last, 0)),
+        ((grid.0, north), (0, last)),
+        ((east, north), (0, 0)),
+    ]
+}
+
+fn get_height(
+    heights: &HashMap<CellExtGrid, LandHeights>,
+    grid: CellExtGrid,
+    (row, column): LandVertex,
+) -> Result<f32> {
+    heights
+        .get(&grid)
+        .and_then(|land_heights| land_heights.get(row))
+        .and_then(|land_row| land_row.get(column))
+        .copied()
+        .with_context(|| format!("Bug: failed to get height of LAND {grid:?} at [{row}, {column}]"))
+}
+
+fn set_height(
+    heights: &mut HashMap<CellExtGrid, LandHeights>,
+    grid: CellExtGrid,
+    (row, column): LandVertex,
+    value: f32,
+) -> Result<()> {
+    *heights
+        .get_mut(&grid)
+        .and_then(|land_heights| land_heights.get_mut(row))
+        .and_then(|land_row| land_row.get_mut(column))
+        .with_context(|| {
+            format!("Bug: failed to set height of LAND {grid:?} at [{row}, {column}]")
+        })? = value;
+    Ok(())
+}
+
+fn get_normal(
+    out: &Out,
+    normals: &HashMap<(CellExtGrid, LandVertex), [i8; 3]>,
+    (grid, global_id): (CellExtGrid, usize),
+    (row, column): LandVertex,
+) -> Result<[i8; 3]> {
+    if let Some(&normal) = normals.get(&(grid, (row, column))) {
+        return Ok(normal);
+    }
+    out.land
+        .get(global_id)
+        .and_then(|land| land.0.vertex_normals.data.get(row))
+        .and_then(|land_row| land_row.get(column))
+        .copied()
+        .with_context(|| {
+            format!("Bug: failed to get normal of out.land[{global_id}] at [{row}, {column}]")
+        })
+}
+
+fn set_normal(
+    out: &mut Out,
+    global_id: usize,
+    (row, column): LandVertex,
+    value: [i8; 3],
+) -> Result<()> {
+    *out.land
+        .get_mut(global_id)
+        .and_then(|land| land.0.vertex_normals.data.get_mut(row))
+        .and_then(|land_row| land_row.get_mut(column))
+        .with_context(|| {
+            format!("Bug: failed to set normal of out.land[{global_id}] at [{row}, {column}]")
+        })? = value;
+    Ok(())
+}
+
+fn average_normal(normals: &[[i8; 3]]) -> [i8; 3] {
+    let mut average = [0_i8; 3];
+    let count = i16::try_from(normals.len()).unwrap_or(i16::MAX);
+    for (index, value) in average.iter_mut().enumerate() {
+        let components = normals
+            .iter()
+            .filter_map(|normal| normal.get(index).copied());
+        let first = components.clone().next().unwrap_or_default();
+        *value = components
+            .fold(0_i16, |sum, component| {
+                sum.saturating_add(i16::from(component))
+            })
+            .checked_div(count)
+            .and_then(|mean| i8::try_from(mean).ok())
+            .unwrap_or(first);
+    }
+    average
+}
+
+#[allow(clippy::float_arithmetic)]
+fn average_height(heights: &[f32]) -> f32 {
+    let count = f32::from(u16::try_from(heights.len()).unwrap_or(u16::MAX));
+    heights.iter().sum::<f32>() / count
+}
+
+// COMMENT: the first value of each row is relative to the first value of the previous row, the rest are relative to the previous value of the row
+#[allow(clippy::float_arithmetic)]
+fn decode_heights(vertex_heights: &VertexHeights) -> LandHeights {
+    let mut heights = [[0.0_f32; LAND_SIDE]; LAND_SIDE];
+    let mut row_offset = vertex_heights.offset;
+    for (row, data_row) in heights.iter_mut().zip(vertex_heights.data.iter()) {
+        let mut column_offset = row_offset;
+        for (column, (height, delta)) in row.iter_mut().zip(data_row.iter()).enumerate() {
+            if column == 0 {
+                row_offset += f32::from(*delta);
+                column_offset = row_offset;
+            } else {
+                column_offset += f32::from(*delta);
+            }
+            *height = column_offset;
+        }
+    }
+    heights
+}
+
+#[allow(
+    clippy::float_arithmetic,
+    clippy::as_conversions,
+    clippy::cast_possible_truncation
+)]
+fn encode_heights(heights: &LandHeights, vertex_heights: &mut VertexHeights) -> bool {
+    let mut data = vertex_heights.data;
+    let mut previous_row_start = vertex_heights.offset;
+    for (row, data_row) in heights.iter().zip(data.iter_mut()) {
+        let mut previous = previous_row_start;
+        for (column, (height, delta)) in row.iter().zip(data_row.iter_mut()).enumerate() {
+            let value = (height - previous).round();
+            if !(f32::from(i8::MIN)..=f32::from(i8::MAX)).contains(&value) {
+                return false;
+            }
+            *delta = value as i8;
+            previous += value;
+            if column == 0 {
+                previous_row_start = previous;
+            }
+        }
+    }
+    vertex_heights.data = data;
+    true
+}
+
+fn show_land_seams(
+    seams: &[String],
+    stitch: bool,
+    name: &str,
+    verbosity: u8,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let seams_len = seams.len();
+    let text = format!(
+        "  {} land seam{} {} in \"{}\"",
+        seams_len,
+        if seams_len == 1 { " was" } else { "s were" },
+        if stitch { "stitched" } else { "found" },
+        name
+    );
+    show_record_notes(seams, text, WarningKind::LandSeams, verbosity, cfg, log)
+}
@@ -0,0 +1,351 @@
+use super::{make_header, provenance::PROVENANCE_VERSION};
+use crate::{
+    get_record_key, increment, msg, show_removed_record_ids, Cfg, HeaderText, Log, MastId,
+    PluginNameLow, ProvenanceFile, RefrId,
+};
+use anyhow::{anyhow, Context as _, Result};
+use fs_err::{create_dir_all, read_to_string};
+use hashbrown::HashMap;
+use std::{iter::once, mem::take, path::PathBuf};
+use tes3::esp::{Cell, Dialogue, Plugin, Reference, TES3Object};
+
+type CellRefSources =
+    HashMap<(MastId, RefrId), (PluginNameLow, RefrId, Option<(PluginNameLow, MastId)>)>;
+
+struct UnmergedPlugin {
+    name: String,
+    masters: Vec<(String, u64)>,
+    objects: Vec<TES3Object>,
+    active_dial_low: Option<String>,
+    refs: usize,
+}
+
+impl UnmergedPlugin {
+    fn push(&mut self, object: TES3Object) {
+        if let TES3Object::Dialogue(ref dial) = object {
+            self.active_dial_low = Some(dial.id.to_lowercase());
+        } else if !matches!(object, TES3Object::DialogueInfo(_)) {
+            self.active_dial_low = None;
+        }
+        self.objects.push(object);
+    }
+
+    // COMMENT: infos may win in a plugin that doesn't win their dialogue, so the dialogue is repeated before the first such info
+    fn push_info(&mut self, info: TES3Object, dial: &Dialogue) {
+        if self.active_dial_low.as_deref() != Some(dial.id.to_lowercase().as_str()) {
+            self.push(TES3Object::Dialogue(dial.clone()));
+        }
+        self.push(info);
+    }
+}
+
+#[derive(Default)]
+struct Unmerged {
+    plugins: Vec<UnmergedPlugin>,
+    plugin_ids: HashMap<PluginNameLow, usize>,
+    merged_masters: Vec<(String, u64)>,
+    unattributed_records: Vec<String>,
+    unattributed_refs: usize,
+}
+
+impl Unmerged {
+    fn plugin(&mut self, plugin_id: usize) -> Result<&mut UnmergedPlugin> {
+        self.plugins
+            .get_mut(plugin_id)
+            .with_context(|| format!("Bug: indexing slicing plugins[{plugin_id}]"))
+    }
+
+    // COMMENT: own references get original indices back, external references go to the first contributor that has their master
+    fn split_cell(
+        &mut self,
+        mut cell: Cell,
+        winner: usize,
+        contributors: &[usize],
+        cell_ref_sources: Option<&CellRefSources>,
+    ) -> Result<()> {
+        let mut references: Vec<HashMap<(MastId, RefrId), Reference>> =
+            self.plugins.iter().map(|_| HashMap::new()).collect();
+        for (_, mut reference) in take(&mut cell.references) {
+            let target = if reference.mast_index == 0 {
+                let Some(&(ref plugin_name_low, original_refr_index, ref editor)) =
+                    cell_ref_sources.and_then(|sources| sources.get(&(0, reference.refr_index)))
+                else {
+                    self.unattributed_refs = increment!(self.unattributed_refs);
+                    continue;
+                };
+                reference.refr_index = original_refr_index;
+                // COMMENT: reference edited by another merged plugin is also returned to the editor as its override
+                if let Some(&(ref editor_name_low, editor_mast_index)) = editor.as_ref() {
+                    if let Some(&editor_id) = self.plugin_ids.get(editor_name_low) {
+                        let editor_reference = Reference {
+                            mast_index: editor_mast_index,
+                            ..reference.clone()
+                        };
+                        references
+                            .get_mut(editor_id)
+                            .with_context(|| {
+                                format!("Bug: indexing slicing references[{editor_id}]")
+                            })?
+                            .insert((editor_mast_index, original_refr_index), editor_reference);
+                    }
+                }
+                self.plugin_ids.get(plugin_name_low).copied()
+            } else {
+                self.remap_external_reference(&mut reference, winner, contributors)?
+            };
+            if let Some(plugin_id) = target {
+                references
+                    .get_mut(plugin_id)
+                    .with_context(|| format!("Bug: indexing slicing references[{plugin_id}]"))?
+                    .insert((reference.mast_index, reference.refr_index), reference);
+            } else {
+                self.unattributed_refs = increment!(self.unattributed_refs);
+            }
+        }
+        for (plugin_id, plugin_references) in references.into_iter().enumerate() {
+            if plugin_id == winner
+                || contributors.contains(&plugin_id)
+                || !plugin_references.is_empty()
+            {
+                let plugin = self.plugin(plugin_id)?;
+                plugin.refs = plugin
+                    .refs
+                    .checked_add(plugin_references.len())
+                    .with_context(|| {
+                        format!(
+                            "Bug: overflow adding plugin_references.len() = \"{}\" to plugin.refs = \"{}\"",
+                            plugin_references.len(),
+                            plugin.refs
+                        )
+                    })?;
+                plugin.push(TES3Object::Cell(Cell {
+                    references: plugin_references,
+                    ..cell.clone()
+                }));
+            }
+        }
+        Ok(())
+    }
+
+    fn remap_external_reference(
+        &self,
+        reference: &mut Reference,
+        winner: usize,
+        contributors: &[usize],
+    ) -> Result<Option<usize>> {
+        let Some(&(ref master_name, _)) = usize::try_from(reference.mast_index)
+            .ok()
+            .and_then(|mast_index| self.merged_masters.get(mast_index.saturating_sub(1)))
+        else {
+            return Ok(None);
+        };
+        let master_name_low = master_name.to_lowercase();
+        for &plugin_id in once(&winner).chain(contributors) {
+            let plugin = self
+                .plugins
+                .get(plugin_id)
+                .with_context(|| format!("Bug: indexing slicing plugins[{plugin_id}]"))?;
+            if let Some(position) = plugin
+                .masters
+                .iter()
+                .position(|&(ref name, _)| name.to_lowercase() == master_name_low)
+            {
+                reference.mast_index =
+                    MastId::try_from(increment!(position)).with_context(|| {
+                        format!("Bug: failed to cast {position:?}(position, usize) to u32")
+                    })?;
+                return Ok(Some(plugin_id));
+            }
+        }
+        Ok(None)
+    }
+}
+
+pub fn unmerge(path: &str, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let provenance_name = format!("{path}{}", cfg.guts.provenance_suffix);
+    let provenance_file: ProvenanceFile = toml::from_str(
+        &read_to_string(&provenance_name)
+            .with_context(|| format!("Failed to read provenance file {provenance_name:?}"))?,
+    )
+    .with_context(|| format!("Failed to parse provenance file {provenance_name:?}"))?;
+    if provenance_file.version != PROVENANCE_VERSION {
+        return Err(anyhow!(
+            "Error: provenance file {provenance_name:?} has unsupported version \"{}\", expected version is \"{PROVENANCE_VERSION}\"",
+            provenance_file.version
+        ));
+    }
+    let mut merged_plugin = Plugin::new();
+    merged_plugin
+        .load_path(path)
+        .with_context(|| format!("Failed to read merged plugin {path:?}"))?;
+    let mut unmerged = Unmerged::default();
+    for plugin in provenance_file.plugins {
+        unmerged
+            .plugin_ids
+            .insert(plugin.name.to_lowercase(), unmerged.plugins.len());
+        unmerged.plugins.push(UnmergedPlugin {
+            name: plugin.name,
+            masters: plugin.masters,
+            objects: Vec::new(),
+            active_dial_low: None,
+            refs: 0,
+        });
+    }
+    let mut records: HashMap<String, HashMap<String, (usize, Vec<usize>)>> = HashMap::new();
+    let get_plugin_id = |plugin_name_low: &str| -> Result<usize> {
+        unmerged.plugin_ids.get(plugin_name_low).copied().with_context(|| {
+            format!("Error: provenance file {provenance_name:?} references unknown plugin \"{plugin_name_low}\"")
+        })
+    };
+    for record in provenance_file.records {
+        let winner = get_plugin_id(&record.winner)?;
+        let contributors = record
+            .overridden
+            .iter()
+            .map(|plugin_name_low| get_plugin_id(plugin_name_low))
+            .collect::<Result<_>>()?;
+        records
+            .entry(record.record_type)
+            .or_default()
+            .insert(record.id, (winner, contributors));
+    }
+    let mut ref_sources: HashMap<String, CellRefSources> = HashMap::new();
+    for cell in provenance_file.cells {
+        ref_sources.insert(
+            cell.cell.to_lowercase(),
+            cell.refs
+                .into_iter()
+                .map(|reference| {
+                    (
+                        (reference.mast_index, reference.refr_index),
+                        (
+                            reference.plugin,
+                            reference.original_refr_index,
+                            reference.editor,
+                        ),
+                    )
+                })
+                .collect(),
+        );
+    }
+    let mut active_dial: Option<Dialogue> = None;
+    for object in merged_plugin.objects {
+        if let TES3Object::Header(header) = object {
+            unmerged.merged_masters = header.masters;
+            continue;
+        }
+        if let TES3Object::Dialogue(ref dial) = object {
+            active_dial = Some(dial.clone());
+        }
+        let active_dial_low = active_dial
+            .as_ref()
+            .map_or_else(String::new, |dial| dial.id.to_lowercase());
+        let (record_type, id) = get_record_key(&object, &active_dial_low);
+        let Some(&(winner, ref contributors)) =
+            records.get(record_type).and_then(|ids| ids.get(&id))
+        else {
+            unmerged
+                .unattributed_records
+                .push(format!("    {record_type} {id:?}"));
+            continue;
+        };
+        #[allow(clippy::wildcard_enum_match_arm)]
+        match object {
+            TES3Object::Dialogue(dial) => {
+                unmerged.plugin(winner)?.push(TES3Object::Dialogue(dial));
+            }
+            TES3Object::DialogueInfo(info) => {
+                let dial = active_dial
+                    .as_ref()
+                    .with_context(|| format!("Bug: info {:?} has no dialogue", info.id))?;
+                unmerged
+                    .plugin(winner)?
+                    .push_info(TES3Object::DialogueInfo(info), dial);
+            }
+            TES3Object::Cell(cell) => {
+                unmerged.split_cell(cell, winner, contributors, ref_sources.get(&id))?;
+            }
+            other => unmerged.plugin(winner)?.push(other),
+        }
+    }
+    write_unmerged_plugins(path, unmerged, cfg, log)
+}
+
+fn write_unmerged_plugins(path: &str, unmerged: Unmerged, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let dir = PathBuf::from(format!("{path}{}", cfg.guts.unmerge_dir_suffix));
+    let dry_run = cfg.list_options.dry_run;
+    if !dry_run {
+        create_dir_all(&dir)
+            .with_context(|| format!("Failed to create directory \"{}\"", dir.display()))?;
+    }
+    let description = format!(
+        "{}{path}{}",
+        cfg.guts.header_description_unmerged_prefix, cfg.guts.header_description_unmerged_suffix
+    );
+    let mut written: usize = 0;
+    for plugin in unmerged.plugins {
+        if plugin.objects.is_empty() {
+            continue;
+        }
+        let plugin_path = dir.join(&plugin.name);
+        let num_objects = u32::try_from(plugin.objects.len()).with_context(|| {
+            format!(
+                "Bug: failed to cast {:?}(objects.len(), usize) to u32",
+                plugin.objects.len()
+            )
+        })?;
+        let text = format!(
+            "  Plugin \"{}\" with {num_objects} records and {} references {}",
+            plugin_path.display(),
+            plugin.refs,
+            if dry_run {
+                "would be written"
+            } else {
+                "was written"
+            }
+        );
+        if !dry_run {
+            let header = make_header(
+                &plugin.name,
+                plugin.masters,
+                num_objects,
+                false,
+                HeaderText::new(&cfg.guts.header_author, &description, cfg, log)?,
+                cfg,
+                log,
+            )?;
+            let mut unmerged_plugin = Plugin::new();
+            unmerged_plugin.objects.push(TES3Object::Header(header));
+            unmerged_plugin.objects.extend(plugin.objects);
+            unmerged_plugin.save_path(&plugin_path).with_context(|| {
+                format!(
+                    "Failed to write unmerged plugin \"{}\"",
+                    plugin_path.display()
+                )
+            })?;
+        }
+        msg(text, 1, cfg, log)?;
+        written = increment!(written);
+    }
+    show_removed_record_ids(
+        &unmerged.unattributed_records,
+        "missing provenance",
+        path,
+        1,
+        cfg,
+        log,
+    )?;
+    if unmerged.unattributed_refs != 0 {
+        let text = format!(
+            "Warning: {} references of {path:?} were skipped because their source plugin is unknown",
+            unmerged.unattributed_refs
+        );
+        msg(text, 0, cfg, log)?;
+    }
+    let text = format!(
+        "Plugin {path:?} {} unmerged into {written} plugins in \"{}\"",
+        if dry_run { "would be" } else { "was" },
+        dir.display()
+    );
+    msg(text, 0, cfg, log)
+}
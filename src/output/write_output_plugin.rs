@@ -1,6 +1,9 @@
-use crate::{msg, msg_no_log, references_sorted, Cfg, Helper, Log, Mode, StatsUpdateKind};
+use crate::{
+    diff_plugins, is_cell_properties_equal, msg, msg_no_log, nan_to_zero, references_sorted, Cfg,
+    Helper, Log, Mode, StatsUpdateKind,
+};
 use anyhow::{anyhow, Context as _, Result};
-use fs_err::create_dir_all;
+use fs_err::{create_dir_all, write};
 use std::{
     fmt::Write as _,
     path::{Path, PathBuf},
@@ -21,9 +24,12 @@ pub fn write_output_plugin(
     let name_path = PathBuf::from(name);
     if name_path.exists() {
         if !no_compare {
-            old_plugin
-                .load_path(name)
-                .with_context(|| format!("Failed to read previous output plugin {name:?}"))?;
+            // COMMENT: previous output plugin is already loaded when it was used to seed reference indices
+            if old_plugin.objects.is_empty() {
+                old_plugin
+                    .load_path(name)
+                    .with_context(|| format!("Failed to read previous output plugin {name:?}"))?;
+            }
             let (is_plugin_equal, mut text) = is_plugin_equal_to_previous(
                 name,
                 plugin,
@@ -37,9 +43,14 @@ pub fn write_output_plugin(
                 text = format!("{}. {}", text, cfg.guts.prefix_list_stats);
                 msg(text, 1, cfg, log)?;
                 add_missing_plugin_stats(level, &plugin.objects, h)?;
+                old_plugin.objects.clear();
                 return Ok(());
             }
             msg(text, 0, cfg, log)?;
+            if level == 1 && h.g.list_options.write_diff_report {
+                write_diff_report(name, plugin, old_plugin, dry_run, cfg, log)
+                    .with_context(|| format!("Failed to write diff report for {name:?}"))?;
+            }
             old_plugin.objects.clear();
         }
     } else if let Some(out_dir) = name_path.parent() {
@@ -91,6 +102,15 @@ pub fn write_output_plugin(
             } else {
                 write!(text, ". New game is not required.")?;
             }
+        } else if h.g.ref_map.seeded {
+            if dry_run {
+                write!(text, ". References would keep indices from previous version, so new game would not be required.")?;
+            } else {
+                write!(
+                    text,
+                    ". References kept indices from previous version, so new game is not required."
+                )?;
+            }
         } else if !dry_run {
             write!(
                 text,
@@ -213,14 +233,7 @@ fn is_plugin_equal_to_previous(
             }
             TES3Object::Cell(ref new_cell) => {
                 if let TES3Object::Cell(ref old_cell) = *old {
-                    if new_cell.flags != old_cell.flags
-                        || new_cell.name != old_cell.name
-                        || new_cell.data != old_cell.data
-                        || new_cell.region != old_cell.region
-                        || new_cell.map_color != old_cell.map_color
-                        || new_cell.water_height != old_cell.water_height
-                        || new_cell.atmosphere_data != old_cell.atmosphere_data
-                    {
+                    if !is_cell_properties_equal(old_cell, new_cell) {
                         write!(
                             text,
                             "cell properties were changed in \"{}\".",
@@ -308,6 +321,45 @@ fn is_plugin_equal_to_previous(
     })
 }
 
+fn write_diff_report(
+    name: &str,
+    new_plugin: &Plugin,
+    old_plugin: &Plugin,
+    dry_run: bool,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let diff = diff_plugins(old_plugin, new_plugin);
+    if diff.is_empty() {
+        let text = if diff.unexplained == 0 {
+            format!("Output plugin {name:?} differs from previous version only in order of records, diff report was not written")
+        } else {
+            format!(
+                "Output plugin {name:?} differs from previous version in {} record{} with differences not shown by diff, e.g. NaN coordinates, diff report was not written",
+                diff.unexplained,
+                if diff.unexplained == 1 { "" } else { "s" }
+            )
+        };
+        return msg(text, 0, cfg, log);
+    }
+    let summary = diff.summary();
+    let report_name = format!("{name}{}", cfg.guts.diff_report_suffix);
+    if dry_run {
+        let text = format!("Output plugin {name:?} differences from previous version, {summary}. Diff report {report_name:?} would be written");
+        return msg(text, 0, cfg, log);
+    }
+    write(
+        &report_name,
+        format!(
+            "Output plugin {name:?} differences from previous version, {summary}\n{}",
+            diff.show()?
+        ),
+    )
+    .with_context(|| format!("Failed to write diff report {report_name:?}"))?;
+    let text = format!("Output plugin {name:?} differences from previous version, {summary}. Diff report {report_name:?} was written");
+    msg(text, 0, cfg, log)
+}
+
 fn get_no_compare_and_dry_run(level: u8, h: &Helper) -> Result<(bool, bool)> {
     match level {
         1 => Ok((h.g.list_options.no_compare, h.g.list_options.dry_run)),
@@ -375,13 +427,3 @@ fn strip_author_description_master_sizes(header: &Header) -> Header {
         ..header.clone()
     }
 }
-
-fn nan_to_zero(reference: &Reference) -> Reference {
-    let mut res = reference.to_owned();
-    for float in res.translation.iter_mut().chain(res.rotation.iter_mut()) {
-        if float.is_nan() {
-            *float = 0.0;
-        }
-    }
-    res
-}
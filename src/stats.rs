@@ -12,6 +12,8 @@ pub enum StatsUpdateKind {
     ResultMergeableUnique,
     ResultMergeableTotal,
     Excluded,
+    Itm,
+    Ignored,
 }
 
 macro_rules! make_stats_per_type {
@@ -42,6 +44,7 @@ macro_rules! make_stats_per_type {
                         .and_then(|r| r.checked_sub(self.merged))
                         .and_then(|r| r.checked_sub(self.replaced))
                         .and_then(|r| r.checked_sub(self.excluded))
+                        .and_then(|r| r.checked_sub(self.ignored))
                         .with_context(|| "Bug: overflow calculating self_check unique")?)
                     && (self.total == self.unique.checked_add(self.mergeable_total).with_context(|| "Bug: overflow calculating self_check total")?))
             }
@@ -94,6 +97,10 @@ macro_rules! make_stats_per_type {
                 Ok(self.grass_filtered = self.grass_filtered.checked_add(count).with_context(|| format!("Bug: overflow adding count = \"{count}\" to grass_filtered"))?)
             }
 
+            pub(crate) fn instances_itm_add_count(&mut self, count: usize) -> Result<()> {
+                Ok(self.instances_itm = self.instances_itm.checked_add(count).with_context(|| format!("Bug: overflow adding count = \"{count}\" to instances_itm"))?)
+            }
+
             pub(crate) fn instances_processed_add_count(&mut self, count: usize) -> Result<()> {
                 Ok(self.instances_processed = self.instances_processed.checked_add(count).with_context(|| format!("Bug: overflow adding count = \"{count}\" to instances_processed"))?)
             }
@@ -125,7 +132,10 @@ macro_rules! make_stats_per_type {
                 empty_if_zero!(duplicate, ", ", "removed(dup)");
                 empty_if_zero!(merged, ", ", "merged");
                 empty_if_zero!(replaced, ", ", "replaced");
+                empty_if_zero!(ignored, ", ", "ignored(rule)");
                 empty_if_zero!(grass_filtered, ", ", "instances filtered(grass)");
+                empty_if_zero!(itm, ", ", "identical to master");
+                empty_if_zero!(instances_itm, ", ", "instances identical to master");
                 empty_if_zero!(total, "", "total");
                 empty_if_zero!(instances_total, "(", "instances)");
                 empty_if_zero!(unique, ", ", "unique");
@@ -133,7 +143,7 @@ macro_rules! make_stats_per_type {
                 empty_if_zero!(mergeable_total, ", ", "mergeable(total)");
                 empty_if_zero!(excluded, ", ", "excluded");
 
-                format!("  input({merged_plugins}): {processed}{instances_processed}{duplicate}{merged}{replaced}{grass_filtered}\n  output({result_plugins}): {total}{instances_total}{unique}{mergeable_unique}{mergeable_total}{excluded}{}{:.3}s duration", if result_plugins.is_empty() { "" } else { ", " }, timer.elapsed().as_secs_f64())
+                format!("  input({merged_plugins}): {processed}{instances_processed}{duplicate}{merged}{replaced}{ignored}{grass_filtered}{itm}{instances_itm}\n  output({result_plugins}): {total}{instances_total}{unique}{mergeable_unique}{mergeable_total}{excluded}{}{:.3}s duration", if result_plugins.is_empty() { "" } else { ", " }, timer.elapsed().as_secs_f64())
             }
         }
 
@@ -220,6 +230,19 @@ macro_rules! make_stats {
                         self.$total.grass_filtered_add_count(count)
             }
 
+            pub(crate) fn instances_itm_add_count(&mut self, count: usize) -> Result<()> {
+                        self.$total.instances_itm_add_count(count)
+            }
+
+            pub(crate) fn itm_string(&self, plugin_name: &str) -> Option<String> {
+                let (itm, instances_itm) = (self.$total.itm, self.$total.instances_itm);
+                if itm == 0 && instances_itm == 0 {
+                    None
+                } else {
+                    Some(format!("  Plugin \"{plugin_name}\" contains {itm} record{} and {instances_itm} instance{} identical to master", if itm == 1 { "" } else { "s" }, if instances_itm == 1 { "" } else { "s" }))
+                }
+            }
+
             pub(crate) fn instances_processed_add_count(&mut self, count: usize) -> Result<()> {
                         self.$total.instances_processed_add_count(count)
             }
@@ -272,6 +295,16 @@ macro_rules! make_stats {
                         self.$total.excluded();
                         self.$n.excluded();
                     },
+                    StatsUpdateKind::Itm => {
+                        self.$total.itm();
+                        self.$n.itm();
+                    },
+                    StatsUpdateKind::Ignored => {
+                        self.$total.processed();
+                        self.$total.ignored();
+                        self.$n.processed();
+                        self.$n.ignored();
+                    },
                 }
             }
             )+
@@ -288,5 +321,5 @@ macro_rules! make_stats {
 
 }
 
-make_stats_per_type!(StatsPerType; usize; merged_plugins, result_plugins, processed, duplicate, merged, replaced, unique, mergeable_unique, mergeable_total, total, excluded, instances_processed, instances_total, grass_filtered);
+make_stats_per_type!(StatsPerType; usize; merged_plugins, result_plugins, processed, duplicate, merged, replaced, ignored, unique, mergeable_unique, mergeable_total, total, excluded, instances_processed, instances_total, grass_filtered, itm, instances_itm);
 make_stats!(Stats; StatsPerType; total; tes3, gmst, glob, clas, fact, race, soun, sndg, skil, mgef, scpt, regn, bsgn, sscr, ltex, spel, stat, door, misc, weap, cont, crea, body, ligh, ench, npc_, armo, clot, repa, acti, appa, lock, prob, ingr, book, alch, levi, levc, cell, land, pgrd, dial, info);
@@ -4,17 +4,29 @@ use hashbrown::{HashMap, HashSet};
 use std::{path::PathBuf, time::SystemTime};
 use tes3::esp::{EffectId, Reference, SkillId, Static};
 pub mod dial;
+pub mod diff;
 pub mod helper;
 pub mod list_options;
+pub mod manifest;
+pub mod master_records;
 pub mod mode;
 pub mod out;
+pub mod parsed_plugins;
+pub mod plugin_cache;
+pub mod provenance;
+pub mod ref_map;
 pub mod turn_normal_grass;
+pub mod warning;
 use dial::{Dial, DialMeta};
 use list_options::ListOptions;
+use master_records::MasterRecords;
 use mode::Mode;
+use provenance::Provenance;
+use ref_map::RefMap;
 use turn_normal_grass::TurnNormalGrass;
 
 pub type CellExtGrid = (i32, i32);
+pub type GrassGridRect = (CellExtGrid, CellExtGrid);
 pub type CellIntNameLow = String;
 pub type GlobalRecordId = usize;
 pub type InfoId = usize;
@@ -59,6 +71,10 @@ pub struct HelperGlobal {
     pub(crate) r: HelperRecords,
     pub(crate) turn_normal_grass: HashMap<String, TurnNormalGrass>,
     pub(crate) found_stat_ids: HashSet<String>,
+    pub(crate) record_rules_ignored: Vec<String>,
+    pub(crate) master_records: MasterRecords,
+    pub(crate) ref_map: RefMap,
+    pub(crate) provenance: Provenance,
     pub(crate) config_index: usize,
 }
 
@@ -83,7 +99,6 @@ pub struct RegexPluginInfo {
 
 #[derive(Clone, Default)]
 pub struct PluginInfo {
-    #[allow(dead_code)]
     pub(crate) id: usize,
     pub(crate) name: PluginName,
     pub(crate) name_low: PluginNameLow,
@@ -215,6 +230,8 @@ pub struct HelperRecords {
     pub(crate) pgrd: HashMap<RecordNameLow, GlobalRecordId>,
     pub(crate) dials: HashMap<RecordNameLow, DialMeta>,
     pub(crate) infos: HashMap<InfoName, RecordNameLow>,
+    pub(crate) record_rule_winners: HashSet<(&'static str, RecordNameLow)>,
+    pub(crate) record_rule_winner_plugins: HashMap<(&'static str, RecordNameLow), PluginNameLow>,
 }
         impl HelperRecords {
             pub(crate) fn clear(&mut self) {
@@ -225,7 +242,7 @@ pub struct HelperRecords {
     };
 }
 
-make_helper_records!(gmst, glob, clas, fact, race, soun, sndg, scpt, regn, bsgn, sscr, ltex, spel, stat, door, misc, weap, cont, crea, body, ligh, ench, npc_, armo, clot, repa, acti, appa, lock, prob, ingr, book, alch, levi, levc; skil, mgef, int_cells, ext_cells, ext_ref_sources, moved_instances, land, pgrd, dials, infos);
+make_helper_records!(gmst, glob, clas, fact, race, soun, sndg, scpt, regn, bsgn, sscr, ltex, spel, stat, door, misc, weap, cont, crea, body, ligh, ench, npc_, armo, clot, repa, acti, appa, lock, prob, ingr, book, alch, levi, levc; skil, mgef, int_cells, ext_cells, ext_ref_sources, moved_instances, land, pgrd, dials, infos, record_rule_winners, record_rule_winner_plugins);
 
 fn get_plugin_info(path: PathBuf, id: usize) -> Result<PluginInfo> {
     match path.file_name() {
@@ -11,6 +11,7 @@ pub struct DialMeta {
 pub struct Dial {
     pub(crate) dialogue: Dialogue,
     pub(crate) info: Vec<DialogueInfo>,
+    pub(crate) info_plugin_ids: Vec<usize>,
     pub(crate) excluded_infos: Vec<usize>,
     pub(crate) dialogue_type: DialDialogueType,
 }
@@ -21,6 +22,7 @@ impl Dial {
             dialogue_type: DialDialogueType::new(dial.dialogue_type),
             dialogue: dial,
             info: Vec::new(),
+            info_plugin_ids: Vec::new(),
             excluded_infos: Vec::new(),
         }
     }
@@ -0,0 +1,125 @@
+use super::{MastId, RefrId};
+use anyhow::{Context as _, Result};
+use hashbrown::{hash_map::Entry, HashMap};
+use std::fmt::Write as _;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+    Moved,
+}
+
+impl DiffKind {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Changed => "changed",
+            Self::Moved => "moved",
+        }
+    }
+}
+
+pub struct RecordDiff {
+    pub(crate) record_type: &'static str,
+    pub(crate) id: String,
+    pub(crate) kind: DiffKind,
+    pub(crate) note: String,
+}
+
+pub struct RefDiff {
+    pub(crate) cell: String,
+    pub(crate) mast_index: MastId,
+    pub(crate) refr_index: RefrId,
+    pub(crate) id: String,
+    pub(crate) kind: DiffKind,
+    pub(crate) note: String,
+}
+
+#[derive(Default)]
+pub struct PluginDiff {
+    pub(crate) records: Vec<RecordDiff>,
+    pub(crate) refs: Vec<RefDiff>,
+    // COMMENT: records that differ only in what is not reported, e.g. NaN coordinates of references
+    pub(crate) unexplained: usize,
+}
+
+impl PluginDiff {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.records.is_empty() && self.refs.is_empty()
+    }
+
+    pub(crate) fn summary(&self) -> String {
+        let count_records = |kind: DiffKind| {
+            self.records
+                .iter()
+                .filter(|record| record.kind == kind)
+                .count()
+        };
+        let count_refs = |kind: DiffKind| self.refs.iter().filter(|r| r.kind == kind).count();
+        format!(
+            "records: {} added, {} removed, {} changed; references: {} added, {} removed, {} changed, {} moved",
+            count_records(DiffKind::Added),
+            count_records(DiffKind::Removed),
+            count_records(DiffKind::Changed),
+            count_refs(DiffKind::Added),
+            count_refs(DiffKind::Removed),
+            count_refs(DiffKind::Changed),
+            count_refs(DiffKind::Moved),
+        )
+    }
+
+    // COMMENT: records are grouped by type and sorted by ID, references are grouped by cell in order of encounter
+    pub(crate) fn show(&self) -> Result<String> {
+        let mut text = String::new();
+        let mut records: Vec<&RecordDiff> = self.records.iter().collect();
+        records.sort_by(|a, b| (a.record_type, &a.id).cmp(&(b.record_type, &b.id)));
+        let mut record_type = "";
+        for record in records {
+            if record.record_type != record_type {
+                record_type = record.record_type;
+                writeln!(text, "{record_type}:")?;
+            }
+            writeln!(
+                text,
+                "  {} {:?}{}",
+                record.kind.as_str(),
+                record.id,
+                record.note
+            )?;
+        }
+        let mut cell_ids: HashMap<&str, usize> = HashMap::new();
+        let mut cells: Vec<(&str, Vec<&RefDiff>)> = Vec::new();
+        for reference in &self.refs {
+            let cell_id = match cell_ids.entry(reference.cell.as_str()) {
+                Entry::Occupied(o) => *o.get(),
+                Entry::Vacant(v) => {
+                    cells.push((reference.cell.as_str(), Vec::new()));
+                    *v.insert(cells.len().saturating_sub(1))
+                }
+            };
+            cells
+                .get_mut(cell_id)
+                .with_context(|| format!("Bug: indexing slicing cells[{cell_id}]"))?
+                .1
+                .push(reference);
+        }
+        for (cell, refs) in cells {
+            writeln!(text, "CELL {cell:?} references:")?;
+            for reference in refs {
+                writeln!(
+                    text,
+                    "  {} ({}, {}) {:?}{}",
+                    reference.kind.as_str(),
+                    reference.mast_index,
+                    reference.refr_index,
+                    reference.id,
+                    reference.note
+                )?;
+            }
+        }
+        Ok(text)
+    }
+}
@@ -5,7 +5,6 @@ use super::{
 use crate::{msg, msg_no_log, show_ignored_ref_errors, Cfg, Log, StatsUpdateKind};
 use anyhow::{anyhow, Context as _, Result};
 use std::{path::PathBuf, time::Instant};
-use tes3::esp::Plugin;
 
 #[derive(Default)]
 pub struct Helper {
@@ -34,6 +33,10 @@ impl Helper {
         self.g.r.clear();
         self.g.turn_normal_grass.clear();
         self.g.found_stat_ids.clear();
+        self.g.record_rules_ignored.clear();
+        self.g.master_records.clear();
+        self.g.ref_map.clear();
+        self.g.provenance.clear();
         self.g.config_index = usize::MAX;
     }
 
@@ -52,6 +55,11 @@ impl Helper {
     pub(crate) fn local_commit(&mut self, cfg: &Cfg, log: &mut Log) -> Result<()> {
         self.g.stats.add_merged_plugin()?;
         self.g.stats.add(&self.l.stats)?;
+        if self.g.list_options.clean_itm {
+            if let Some(text) = self.l.stats.itm_string(&self.l.plugin_info.name) {
+                msg(text, 1, cfg, log)?;
+            }
+        }
         if !self.g.list_options.no_show_missing_refs {
             show_ignored_ref_errors(
                 &self.l.ignored_cell_errors,
@@ -72,13 +80,7 @@ impl Helper {
         Ok(())
     }
 
-    pub(crate) fn global_commit(
-        &mut self,
-        timer: Instant,
-        new_plugin: &mut Plugin,
-        cfg: &Cfg,
-        log: &mut Log,
-    ) -> Result<()> {
+    pub(crate) fn global_commit(&mut self, timer: Instant, cfg: &Cfg, log: &mut Log) -> Result<()> {
         self.g.stats.add_result_plugin()?;
         if !self.g.stats.self_check()? {
             return Err(anyhow!(
@@ -102,9 +104,7 @@ impl Helper {
             msg_no_log(&text, 1, cfg);
         }
         text = format!("{}{}", text, self.g.stats);
-        msg(text, 3, cfg, log)?;
-        new_plugin.objects.clear();
-        Ok(())
+        msg(text, 3, cfg, log)
     }
 
     pub(crate) fn total_commit(&mut self, timer: Instant, cfg: &Cfg, log: &mut Log) -> Result<()> {
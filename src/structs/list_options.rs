@@ -1,9 +1,12 @@
-use super::Mode;
+use super::{GrassGridRect, Mode};
 use crate::{
     get_append_to_use_load_order_string, get_base_dir_path, get_game_config_string,
-    get_skip_from_use_load_order_string, msg, increment, Cfg, Log
+    get_grass_density, get_grass_exclude_grids, get_grass_exclude_meshes,
+    get_grass_exclude_regions, get_skip_from_use_load_order_string, msg, increment,
+    show_grass_list_options, Cfg, Log
 };
 use anyhow::{Context as _, Result};
+use regex::Regex;
 use std::{
     fmt::Write as _,
     path::PathBuf,
@@ -43,6 +46,28 @@ pub struct ListOptions {
     pub(crate) force_dial_type: bool,
     pub(crate) ignore_important_errors: bool,
     pub(crate) insufficient_merge: bool,
+    pub(crate) merge_leveled_lists: bool,
+    pub(crate) merge_actors: bool,
+    pub(crate) merge_inventories: bool,
+    pub(crate) merge_races_birthsigns: bool,
+    pub(crate) merge_lands: bool,
+    pub(crate) check_land_seams: bool,
+    pub(crate) stitch_land_seams: bool,
+    pub(crate) merge_pathgrids: bool,
+    pub(crate) repair_info_chains: bool,
+    pub(crate) clean_itm: bool,
+    pub(crate) exclude_evil_gmsts: bool,
+    pub(crate) check_duplicate_refs: bool,
+    pub(crate) exclude_duplicate_refs: bool,
+    pub(crate) stable_ref_indices: bool,
+    pub(crate) write_provenance: bool,
+    pub(crate) write_ref_map: bool,
+    pub(crate) write_diff_report: bool,
+    pub(crate) incremental: bool,
+    pub(crate) grass_density: u8,
+    pub(crate) grass_exclude_regions: Vec<String>,
+    pub(crate) grass_exclude_grids: Vec<GrassGridRect>,
+    pub(crate) grass_exclude_meshes: Vec<Regex>,
     pub(crate) append_to_use_load_order: String,
     pub(crate) skip_from_use_load_order: String,
     pub(crate) indirect: IndirectListOptions,
@@ -62,6 +87,7 @@ impl ListOptions {
         if !self.config.is_empty() {
             write!(text, ", config = \"{}\"", self.config)?;
         };
+        show_grass_list_options(&mut text, self)?;
         if !self.append_to_use_load_order.is_empty() {
             write!(
                 text,
@@ -104,7 +130,25 @@ impl ListOptions {
             regex_sort_by_name,
             force_dial_type,
             ignore_important_errors,
-            insufficient_merge
+            insufficient_merge,
+            merge_leveled_lists,
+            merge_actors,
+            merge_inventories,
+            merge_races_birthsigns,
+            merge_lands,
+            check_land_seams,
+            stitch_land_seams,
+            merge_pathgrids,
+            repair_info_chains,
+            clean_itm,
+            exclude_evil_gmsts,
+            check_duplicate_refs,
+            exclude_duplicate_refs,
+            stable_ref_indices,
+            write_provenance,
+            write_ref_map,
+            write_diff_report,
+            incremental
         );
         Ok(text)
     }
@@ -138,6 +182,18 @@ impl ListOptions {
             } else if arg_low.starts_with(&cfg.guts.list_options_prefix_config) {
                 list_options.config = get_game_config_string(arg, cfg)
                     .with_context(|| format!("Failed to get game config from {arg:?}"))?;
+            } else if arg_low.starts_with(&cfg.guts.list_options_prefix_grass_density) {
+                list_options.grass_density = get_grass_density(arg, cfg)
+                    .with_context(|| format!("Failed to get grass_density from {arg:?}"))?;
+            } else if arg_low.starts_with(&cfg.guts.list_options_prefix_grass_exclude_regions) {
+                list_options.grass_exclude_regions = get_grass_exclude_regions(arg, cfg)
+                    .with_context(|| format!("Failed to get grass_exclude_regions from {arg:?}"))?;
+            } else if arg_low.starts_with(&cfg.guts.list_options_prefix_grass_exclude_grids) {
+                list_options.grass_exclude_grids = get_grass_exclude_grids(arg, cfg)
+                    .with_context(|| format!("Failed to get grass_exclude_grids from {arg:?}"))?;
+            } else if arg_low.starts_with(&cfg.guts.list_options_prefix_grass_exclude_meshes) {
+                list_options.grass_exclude_meshes = get_grass_exclude_meshes(arg, cfg)
+                    .with_context(|| format!("Failed to get grass_exclude_meshes from {arg:?}"))?;
             } else if arg_low.starts_with(&cfg.guts.list_options_prefix_append_to_use_load_order) {
                 list_options.append_to_use_load_order =
                     get_append_to_use_load_order_string(arg, cfg).with_context(|| {
@@ -203,6 +259,42 @@ impl ListOptions {
                     "no_ignore_important_errors" => list_options.ignore_important_errors = false,
                     "insufficient_merge" => list_options.insufficient_merge = true,
                     "no_insufficient_merge" => list_options.insufficient_merge = false,
+                    "merge_leveled_lists" => list_options.merge_leveled_lists = true,
+                    "no_merge_leveled_lists" => list_options.merge_leveled_lists = false,
+                    "merge_actors" => list_options.merge_actors = true,
+                    "no_merge_actors" => list_options.merge_actors = false,
+                    "merge_inventories" => list_options.merge_inventories = true,
+                    "no_merge_inventories" => list_options.merge_inventories = false,
+                    "merge_races_birthsigns" => list_options.merge_races_birthsigns = true,
+                    "no_merge_races_birthsigns" => list_options.merge_races_birthsigns = false,
+                    "merge_lands" => list_options.merge_lands = true,
+                    "no_merge_lands" => list_options.merge_lands = false,
+                    "check_land_seams" => list_options.check_land_seams = true,
+                    "no_check_land_seams" => list_options.check_land_seams = false,
+                    "stitch_land_seams" => list_options.stitch_land_seams = true,
+                    "no_stitch_land_seams" => list_options.stitch_land_seams = false,
+                    "merge_pathgrids" => list_options.merge_pathgrids = true,
+                    "no_merge_pathgrids" => list_options.merge_pathgrids = false,
+                    "repair_info_chains" => list_options.repair_info_chains = true,
+                    "no_repair_info_chains" => list_options.repair_info_chains = false,
+                    "clean_itm" => list_options.clean_itm = true,
+                    "no_clean_itm" => list_options.clean_itm = false,
+                    "exclude_evil_gmsts" => list_options.exclude_evil_gmsts = true,
+                    "no_exclude_evil_gmsts" => list_options.exclude_evil_gmsts = false,
+                    "check_duplicate_refs" => list_options.check_duplicate_refs = true,
+                    "no_check_duplicate_refs" => list_options.check_duplicate_refs = false,
+                    "exclude_duplicate_refs" => list_options.exclude_duplicate_refs = true,
+                    "no_exclude_duplicate_refs" => list_options.exclude_duplicate_refs = false,
+                    "stable_ref_indices" => list_options.stable_ref_indices = true,
+                    "no_stable_ref_indices" => list_options.stable_ref_indices = false,
+                    "write_provenance" => list_options.write_provenance = true,
+                    "no_write_provenance" => list_options.write_provenance = false,
+                    "write_ref_map" => list_options.write_ref_map = true,
+                    "no_write_ref_map" => list_options.write_ref_map = false,
+                    "write_diff_report" => list_options.write_diff_report = true,
+                    "no_write_diff_report" => list_options.write_diff_report = false,
+                    "incremental" => list_options.incremental = true,
+                    "no_incremental" => list_options.incremental = false,
                     _ => break,
                 }
             }
@@ -237,6 +329,22 @@ impl ListOptions {
                 self.indirect.base_dir = self.base_dir_indirect.clone();
             }
         }
+        if self.stitch_land_seams && !self.check_land_seams {
+            writeln!(&mut text, "{prefix} set \"check_land_seams\" due to \"stitch_land_seams\"")?;
+            self.check_land_seams = true;
+        }
+        if self.exclude_duplicate_refs && !self.check_duplicate_refs {
+            writeln!(&mut text, "{prefix} set \"check_duplicate_refs\" due to \"exclude_duplicate_refs\"")?;
+            self.check_duplicate_refs = true;
+        }
+        if self.write_diff_report && self.no_compare {
+            writeln!(&mut text, "{prefix} unset \"no_compare\" due to \"write_diff_report\"")?;
+            self.no_compare = false;
+        }
+        if self.stable_ref_indices && self.reindex {
+            writeln!(&mut text, "{prefix} unset \"reindex\" due to \"stable_ref_indices\"")?;
+            self.reindex = false;
+        }
         if self.force_dial_type && self.insufficient_merge {
             writeln!(&mut text, "{prefix} unset \"force_dial_type\" due to \"insufficient_merge\"")?;
             self.force_dial_type = false;
@@ -251,6 +359,18 @@ impl ListOptions {
                 self.insufficient_merge = true;
             }
         }
+        if self.incremental && self.clean_itm {
+            writeln!(&mut text, "{prefix} unset \"incremental\" due to \"clean_itm\"")?;
+            self.incremental = false;
+        }
+        if self.incremental && self.turn_normal_grass {
+            writeln!(&mut text, "{prefix} unset \"incremental\" due to \"turn_normal_grass\"")?;
+            self.incremental = false;
+        }
+        if self.incremental && self.merge_pathgrids && cfg.advanced.pathgrid_static_distance > 0 {
+            writeln!(&mut text, "{prefix} unset \"incremental\" due to \"merge_pathgrids\" with \"pathgrid_static_distance\"")?;
+            self.incremental = false;
+        }
         if !text.is_empty() {
             msg(text, 1, cfg, log)?;
         }
@@ -258,3 +378,46 @@ impl ListOptions {
     }
 }
 
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use crate::get_test_cfg_and_log;
+
+    #[test]
+    fn incremental_is_unset_with_untracked_inputs() {
+        let (cfg, _) = get_test_cfg_and_log();
+        for option in ["clean_itm", "turn_normal_grass"] {
+            let list_options = cfg
+                .parse_list_options(&["incremental", option])
+                .expect("failed to parse list options");
+            assert!(
+                !list_options.incremental,
+                "incremental should be unset with {option:?}"
+            );
+        }
+        let list_options = cfg
+            .parse_list_options(&["incremental"])
+            .expect("failed to parse list options");
+        assert!(list_options.incremental, "incremental alone should be kept");
+    }
+
+    #[test]
+    fn incremental_is_unset_when_pathgrids_are_checked_against_masters() {
+        let (mut cfg, _) = get_test_cfg_and_log();
+        let list_options = cfg
+            .parse_list_options(&["incremental", "merge_pathgrids"])
+            .expect("failed to parse list options");
+        assert!(
+            list_options.incremental,
+            "incremental should be kept without pathgrid_static_distance"
+        );
+        cfg.advanced.pathgrid_static_distance = 64;
+        let list_options = cfg
+            .parse_list_options(&["incremental", "merge_pathgrids"])
+            .expect("failed to parse list options");
+        assert!(
+            !list_options.incremental,
+            "incremental should be unset with pathgrid_static_distance"
+        );
+    }
+}
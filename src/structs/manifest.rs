@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, PartialEq, Eq, Serialize)]
+pub struct ManifestFile {
+    pub(crate) version: String,
+    pub(crate) options: String,
+    pub(crate) settings: Option<ManifestInput>,
+    pub(crate) output: Option<ManifestInput>,
+    pub(crate) plugins: Vec<ManifestInput>,
+}
+
+#[derive(Deserialize, PartialEq, Eq, Serialize)]
+pub struct ManifestInput {
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) mtime_secs: u64,
+    pub(crate) mtime_nanos: u32,
+}
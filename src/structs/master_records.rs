@@ -0,0 +1,38 @@
+use super::{CellExtGrid, CellIntNameLow, MasterNameLow, RecordNameLow};
+use hashbrown::HashMap;
+use tes3::esp::{
+    Activator, Alchemy, Apparatus, Armor, Birthsign, Bodypart, Book, Cell, Class, Clothing,
+    Container, Creature, Door, EffectId, Enchanting, Faction, GameSetting, GlobalVariable,
+    Ingredient, LeveledCreature, LeveledItem, Light, Lockpick, MagicEffect, MiscItem, Npc,
+    PathGrid, Probe, Race, Region, RepairItem, Script, Skill, SkillId, Sound, SoundGen, Spell,
+    StartScript, Static, Weapon,
+};
+
+macro_rules! make_master_records {
+    ($($type:ident, $obj:ident);+) => {
+        #[derive(Default)]
+        pub struct MasterRecords {
+            pub(crate) loaded: Vec<MasterNameLow>,
+            $(pub(crate) $type: HashMap<RecordNameLow, $obj>,)+
+            pub(crate) skil: HashMap<SkillId, Skill>,
+            pub(crate) mgef: HashMap<EffectId, MagicEffect>,
+            pub(crate) int_cells: HashMap<CellIntNameLow, Cell>,
+            pub(crate) ext_cells: HashMap<CellExtGrid, Cell>,
+            pub(crate) pgrd: HashMap<(RecordNameLow, CellExtGrid), PathGrid>,
+        }
+
+        impl MasterRecords {
+            pub(crate) fn clear(&mut self) {
+                self.loaded.clear();
+                $(self.$type.clear();)+
+                self.skil.clear();
+                self.mgef.clear();
+                self.int_cells.clear();
+                self.ext_cells.clear();
+                self.pgrd.clear();
+            }
+        }
+    };
+}
+
+make_master_records!(gmst, GameSetting; glob, GlobalVariable; clas, Class; fact, Faction; race, Race; soun, Sound; sndg, SoundGen; scpt, Script; regn, Region; bsgn, Birthsign; sscr, StartScript; spel, Spell; stat, Static; door, Door; misc, MiscItem; weap, Weapon; cont, Container; crea, Creature; body, Bodypart; ligh, Light; ench, Enchanting; npc_, Npc; armo, Armor; clot, Clothing; repa, RepairItem; acti, Activator; appa, Apparatus; lock, Lockpick; prob, Probe; ingr, Ingredient; book, Book; alch, Alchemy; levi, LeveledItem; levc, LeveledCreature);
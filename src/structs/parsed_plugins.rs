@@ -0,0 +1,145 @@
+use crate::{load_plugin, Cfg};
+use anyhow::{anyhow, Context as _, Result};
+use hashbrown::HashMap;
+use std::{
+    io::Result as IOResult,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver, Sender},
+};
+use tes3::esp::Plugin;
+
+#[derive(Clone, Copy)]
+pub enum SkipReason {
+    IgnoredExtension,
+    TngContent,
+    SkipFromUseLoadOrder,
+}
+
+impl SkipReason {
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::IgnoredExtension => "because it has extension to ignore",
+            Self::TngContent => "trying to recreate it from scratch",
+            Self::SkipFromUseLoadOrder => "due to \"skip_from_use_load_order\"",
+        }
+    }
+}
+
+// COMMENT: plugins are parsed in parallel up to a window ahead, while processing still takes them one by one in load order
+pub struct ParsedPlugins {
+    pub(crate) paths: Vec<Result<(PathBuf, String), SkipReason>>,
+    pub(crate) window: usize,
+    pub(crate) cache_dir: Option<PathBuf>,
+    pub(crate) parsed: HashMap<usize, IOResult<Plugin>>,
+    pub(crate) next_to_spawn: usize,
+    pub(crate) in_flight: usize,
+    pub(crate) sender: Sender<(usize, IOResult<Plugin>)>,
+    pub(crate) receiver: Receiver<(usize, IOResult<Plugin>)>,
+}
+
+impl ParsedPlugins {
+    pub(crate) fn new(
+        plugin_list: &[String],
+        base_dir: &Path,
+        tng_content_name_low: &str,
+        skip_plugin_name_low: &str,
+        cfg: &Cfg,
+    ) -> Self {
+        let paths = plugin_list
+            .iter()
+            .map(|plugin_name| {
+                let plugin_name_low = plugin_name.to_lowercase();
+                if cfg
+                    .guts
+                    .plugin_extensions_to_ignore
+                    .iter()
+                    .any(|ext| plugin_name_low.ends_with(ext))
+                {
+                    Err(SkipReason::IgnoredExtension)
+                } else if !tng_content_name_low.is_empty()
+                    && plugin_name_low.ends_with(tng_content_name_low)
+                {
+                    Err(SkipReason::TngContent)
+                } else if !skip_plugin_name_low.is_empty()
+                    && plugin_name_low.ends_with(skip_plugin_name_low)
+                {
+                    Err(SkipReason::SkipFromUseLoadOrder)
+                } else {
+                    let plugin_pathbuf = base_dir.join(plugin_name);
+                    let plugin_pathstring = plugin_pathbuf.to_string_lossy().into_owned();
+                    Ok((plugin_pathbuf, plugin_pathstring))
+                }
+            })
+            .collect();
+        let (sender, receiver) = channel();
+        Self {
+            paths,
+            window: cfg.guts.plugins_parsing_window.max(1),
+            cache_dir: cfg
+                .plugin_cache
+                .enabled
+                .then(|| cfg.plugin_cache.dir.clone()),
+            parsed: HashMap::new(),
+            next_to_spawn: 0,
+            in_flight: 0,
+            sender,
+            receiver,
+        }
+    }
+
+    pub(crate) fn get_skip_reason(&self, plugin_index: usize) -> Result<Option<SkipReason>> {
+        self.paths
+            .get(plugin_index)
+            .map(|paths| paths.as_ref().err().copied())
+            .with_context(|| format!("Bug: indexing slicing paths[{plugin_index}]"))
+    }
+
+    pub(crate) fn get_paths(&self, plugin_index: usize) -> Result<(PathBuf, String)> {
+        self.paths
+            .get(plugin_index)
+            .and_then(|paths| paths.as_ref().ok().cloned())
+            .with_context(|| format!("Bug: failed to get path of plugin with index {plugin_index}"))
+    }
+
+    // COMMENT: parsed plugins that are not taken yet and plugins being parsed never exceed the window
+    fn refill(&mut self) {
+        while self.next_to_spawn < self.paths.len()
+            && self.in_flight.saturating_add(self.parsed.len()) < self.window
+        {
+            let index = self.next_to_spawn;
+            self.next_to_spawn = index.saturating_add(1);
+            if let Some(&Ok((_, ref plugin_pathstring))) = self.paths.get(index) {
+                let plugin_pathstring = plugin_pathstring.clone();
+                let cache_dir = self.cache_dir.clone();
+                let sender = self.sender.clone();
+                rayon::spawn(move || {
+                    let plugin = load_plugin(&plugin_pathstring, cache_dir.as_deref());
+                    drop(sender.send((index, plugin)));
+                });
+                self.in_flight = self.in_flight.saturating_add(1);
+            }
+        }
+    }
+
+    pub(crate) fn take(&mut self, plugin_index: usize) -> Result<IOResult<Plugin>> {
+        loop {
+            self.parsed.retain(|&index, _| index >= plugin_index);
+            self.refill();
+            if let Some(plugin) = self.parsed.remove(&plugin_index) {
+                self.refill();
+                return Ok(plugin);
+            }
+            if self.in_flight == 0 {
+                return Err(anyhow!(
+                    "Bug: failed to parse plugin with index {plugin_index}"
+                ));
+            }
+            let (index, plugin) = self
+                .receiver
+                .recv()
+                .with_context(|| "Bug: failed to receive parsed plugin")?;
+            self.in_flight = self.in_flight.saturating_sub(1);
+            self.parsed.insert(index, plugin);
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, PartialEq, Eq, Serialize)]
+pub struct PluginCacheKey {
+    pub(crate) version: String,
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) mtime_secs: u64,
+    pub(crate) mtime_nanos: u32,
+}
@@ -0,0 +1,92 @@
+use super::{MastId, PluginNameLow, RecordNameLow, RefrId};
+use hashbrown::{hash_map::Entry, HashMap};
+use serde::{Deserialize, Serialize};
+
+#[derive(Default)]
+pub struct Provenance {
+    pub(crate) records: HashMap<(&'static str, RecordNameLow), RecordProvenance>,
+    pub(crate) order: Vec<(&'static str, RecordNameLow)>,
+    pub(crate) masters: HashMap<usize, Vec<(String, u64)>>,
+    pub(crate) ref_editors: HashMap<(PluginNameLow, RefrId), (PluginNameLow, MastId)>,
+}
+
+pub struct RecordProvenance {
+    pub(crate) winner: usize,
+    pub(crate) plugins: Vec<usize>,
+}
+
+impl Provenance {
+    pub(crate) fn clear(&mut self) {
+        self.records.clear();
+        self.order.clear();
+        self.masters.clear();
+        self.ref_editors.clear();
+    }
+
+    pub(crate) fn add(
+        &mut self,
+        record_type: &'static str,
+        id: RecordNameLow,
+        plugin_id: usize,
+        is_winner: bool,
+    ) {
+        match self.records.entry((record_type, id)) {
+            Entry::Vacant(v) => {
+                self.order.push(v.key().clone());
+                v.insert(RecordProvenance {
+                    winner: plugin_id,
+                    plugins: vec![plugin_id],
+                });
+            }
+            Entry::Occupied(mut o) => {
+                let record = o.get_mut();
+                if is_winner {
+                    record.winner = plugin_id;
+                }
+                if !record.plugins.contains(&plugin_id) {
+                    record.plugins.push(plugin_id);
+                }
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProvenanceFile {
+    pub(crate) version: u32,
+    pub(crate) plugins: Vec<ProvenancePlugin>,
+    pub(crate) records: Vec<ProvenanceRecord>,
+    pub(crate) cells: Vec<ProvenanceCell>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProvenancePlugin {
+    pub(crate) name: String,
+    pub(crate) masters: Vec<(String, u64)>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProvenanceRecord {
+    #[serde(rename = "type")]
+    pub(crate) record_type: String,
+    pub(crate) id: RecordNameLow,
+    pub(crate) winner: PluginNameLow,
+    pub(crate) overridden: Vec<PluginNameLow>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProvenanceCell {
+    pub(crate) cell: String,
+    pub(crate) refs: Vec<ProvenanceRef>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProvenanceRef {
+    pub(crate) refr_index: RefrId,
+    pub(crate) mast_index: MastId,
+    pub(crate) id: String,
+    pub(crate) plugin: PluginNameLow,
+    pub(crate) original_refr_index: RefrId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) editor: Option<(PluginNameLow, MastId)>,
+}
@@ -0,0 +1,146 @@
+use super::{MastId, PluginNameLow, RefrId};
+use crate::increment;
+use anyhow::{anyhow, Context as _, Result};
+use hashbrown::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+
+pub type RefMapKey = (PluginNameLow, MastId, RefrId);
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct RefMapFile {
+    pub(crate) version: u32,
+    pub(crate) plugins: Vec<RefMapPlugin>,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct RefMapPlugin {
+    pub(crate) name: PluginNameLow,
+    pub(crate) refs: Vec<(MastId, RefrId, RefrId)>,
+}
+
+#[derive(Default)]
+pub struct RefMap {
+    pub(crate) previous: HashMap<RefMapKey, RefrId>,
+    pub(crate) seeded: bool,
+    pub(crate) next_refr: RefrId,
+    pub(crate) kept: usize,
+    pub(crate) used: HashSet<RefrId>,
+    pub(crate) current: Vec<(RefMapKey, RefrId)>,
+}
+
+impl RefMap {
+    pub(crate) fn clear(&mut self) {
+        self.previous.clear();
+        self.seeded = false;
+        self.next_refr = 1;
+        self.kept = 0;
+        self.used.clear();
+        self.current.clear();
+    }
+
+    // COMMENT: references known from previous output plugin keep their index, others get fresh indices above all previously used ones
+    pub(crate) fn get_refr(&mut self, key: Option<RefMapKey>) -> Result<RefrId> {
+        let previous_refr = key
+            .as_ref()
+            .and_then(|key| self.previous.get(key))
+            .copied()
+            .filter(|refr| !self.used.contains(refr));
+        let refr = if let Some(refr) = previous_refr {
+            self.kept = increment!(self.kept);
+            refr
+        } else {
+            let refr = self.next_refr;
+            self.next_refr = refr.checked_add(1).ok_or_else(|| {
+                anyhow!(
+                    "Error: limit of {} references per plugin reached. Split the list into smaller parts.",
+                    u32::MAX
+                )
+            })?;
+            refr
+        };
+        self.used.insert(refr);
+        if let Some(key) = key {
+            self.current.push((key, refr));
+        }
+        Ok(refr)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{RefMap, RefMapKey};
+
+    fn get_key(refr_index: u32) -> Option<RefMapKey> {
+        Some(("test.esp".to_owned(), 0, refr_index))
+    }
+
+    fn get_ref_map() -> RefMap {
+        let mut ref_map = RefMap::default();
+        ref_map.clear();
+        ref_map.next_refr = 10;
+        ref_map.previous.insert(("test.esp".to_owned(), 0, 1), 5);
+        ref_map.previous.insert(("test.esp".to_owned(), 0, 2), 5);
+        ref_map.seeded = true;
+        ref_map
+    }
+
+    #[test]
+    fn known_reference_keeps_previous_index() {
+        let mut ref_map = get_ref_map();
+        assert_eq!(
+            ref_map.get_refr(get_key(1)).expect("failed to get index"),
+            5,
+            "known reference should keep its index"
+        );
+        assert_eq!(ref_map.kept, 1, "kept reference wasn't counted");
+        assert_eq!(
+            ref_map.current,
+            [(("test.esp".to_owned(), 0, 1), 5)],
+            "index wasn't recorded for the next reference map"
+        );
+    }
+
+    #[test]
+    fn new_references_get_indices_above_previous_ones() {
+        let mut ref_map = get_ref_map();
+        assert_eq!(
+            ref_map.get_refr(get_key(3)).expect("failed to get index"),
+            10,
+            "new reference should get the next free index"
+        );
+        assert_eq!(
+            ref_map.get_refr(None).expect("failed to get index"),
+            11,
+            "reference without key should get the next free index"
+        );
+        assert_eq!(ref_map.kept, 0, "new references were counted as kept");
+        assert_eq!(
+            ref_map.current.len(),
+            1,
+            "reference without key shouldn't be recorded"
+        );
+    }
+
+    #[test]
+    fn previous_index_is_used_only_once() {
+        let mut ref_map = get_ref_map();
+        let first = ref_map.get_refr(get_key(1)).expect("failed to get index");
+        let second = ref_map.get_refr(get_key(2)).expect("failed to get index");
+        assert_eq!(
+            (first, second),
+            (5, 10),
+            "the same previous index shouldn't be given to two references"
+        );
+    }
+
+    #[test]
+    fn index_overflow_is_an_error() {
+        let mut ref_map = get_ref_map();
+        ref_map.next_refr = u32::MAX;
+        assert!(
+            ref_map.get_refr(None).is_err(),
+            "index overflow should be an error"
+        );
+    }
+}
@@ -0,0 +1,39 @@
+/// Kind of [`Warning`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WarningKind {
+    /// Records changed by several plugins that couldn't be merged automatically.
+    MergeConflicts,
+    /// Land seams between cells.
+    LandSeams,
+    /// Evil GMSTs found in plugins.
+    EvilGmsts,
+    /// Duplicate references in cells.
+    DuplicateRefs,
+    /// Winning plugins of record rules that are not in the list.
+    MissingRuleWinners,
+    /// Broken chains of dialogue infos.
+    InfoChainProblems,
+    /// Pathgrid points near statics.
+    PathgridPointsNearStatics,
+    /// Records excluded from the output plugin.
+    RemovedRecords,
+    /// References missing from masters of probably outdated plugins.
+    IgnoredRefErrors,
+    /// Dialogue type changed by a plugin.
+    DialogueTypeChange,
+    /// Header field truncated to the format's limit.
+    TruncatedHeader,
+    /// Important errors ignored due to "ignore_important_errors".
+    IgnoredError,
+}
+
+/// Warning collected by [`crate::MergeJob::run`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct Warning {
+    /// Kind of warning.
+    pub kind: WarningKind,
+    /// Text of warning, including list of records if any.
+    pub text: String,
+}
@@ -1,4 +1,6 @@
-use crate::{input::process_records, Cfg, Helper, ListOptions, Mode, Out, Plugin};
+use crate::{
+    input::process_records, Cfg, Helper, ListOptions, Mode, Out, ParsedPlugins, WarningKind,
+};
 use anyhow::{anyhow, Context as _, Result};
 use crc::{Crc, CRC_64_ECMA_182};
 use fs_err::{create_dir_all, File};
@@ -7,12 +9,16 @@ use std::{
     io::{BufRead as _, BufReader, Lines},
     path::{Path, PathBuf},
 };
-use tes3::esp::{Cell, CellFlags};
+use tes3::esp::{Cell, CellFlags, TES3Object};
+pub mod diff;
+pub mod grass;
 pub mod header;
+pub mod itm;
 pub mod load_order;
 pub mod log;
 pub mod msg;
 pub mod patterns;
+pub mod plugin_cache;
 pub mod presets;
 pub mod references;
 pub mod tng;
@@ -23,6 +29,7 @@ pub const CRC64: Crc<u64> = Crc::<u64>::new(&CRC_64_ECMA_182);
 pub const SNDG_ID_MAX_LEN: usize = 32;
 pub const SNDG_ID_SUFFIX_LEN: usize = 4;
 pub const SNDG_MAX_SOUND_FLAG: u32 = 7;
+pub const LAND_SIDE: usize = 65;
 
 macro_rules! increment {
     ($($field:ident).+) => {
@@ -158,30 +165,26 @@ pub fn should_skip_list(
 }
 
 pub fn process_plugin(
-    plugin_name: &str,
+    plugin_index: usize,
+    parsed_plugins: &mut ParsedPlugins,
     out: &mut Out,
     name: &str,
     h: &mut Helper,
     cfg: &Cfg,
     log: &mut Log,
 ) -> Result<()> {
-    let (plugin_pathbuf, plugin_pathstring) = get_plugin_pathbuf_pathstring(plugin_name, h);
+    let (plugin_pathbuf, plugin_pathstring) = parsed_plugins.get_paths(plugin_index)?;
     let text = format!("  Processing plugin \"{}\"", &plugin_pathstring);
     msg(text, 2, cfg, log)?;
     h.local_init(plugin_pathbuf, h.g.plugins_processed.len())
         .with_context(|| "Failed to start processing plugin")?;
-    let plugin = Plugin::from_path(&plugin_pathstring)
+    let plugin = parsed_plugins
+        .take(plugin_index)?
         .with_context(|| format!("Failed to read plugin \"{plugin_pathstring}\""))?;
     process_records(plugin, out, name, h, cfg, log)
         .with_context(|| format!("Failed to process records from plugin \"{plugin_pathstring}\"",))
 }
 
-fn get_plugin_pathbuf_pathstring(plugin_name: &str, h: &Helper) -> (PathBuf, String) {
-    let plugin_pathbuf = h.g.list_options.indirect.base_dir.join(plugin_name);
-    let plugin_path = plugin_pathbuf.to_string_lossy().into_owned();
-    (plugin_pathbuf, plugin_path)
-}
-
 pub fn read_lines(filename: &Path) -> Result<Lines<BufReader<File>>> {
     let file = File::open(filename)
         .with_context(|| format!("Failed to open file \"{}\"", filename.display()))?;
@@ -209,6 +212,65 @@ pub fn get_cell_name(cell: &Cell) -> String {
     }
 }
 
+pub fn get_provenance_cell_name(cell: &Cell) -> String {
+    if cell.is_interior() {
+        cell.name.clone()
+    } else {
+        format!("{:?}", cell.data.grid)
+    }
+}
+
+// COMMENT: keys mirror the ones recorded into provenance file during merge
+pub fn get_record_key(object: &TES3Object, active_dial_low: &str) -> (&'static str, String) {
+    match *object {
+        TES3Object::Header(_) => ("TES3", String::new()),
+        TES3Object::GameSetting(ref v) => ("GMST", v.id.to_lowercase()),
+        TES3Object::GlobalVariable(ref v) => ("GLOB", v.id.to_lowercase()),
+        TES3Object::Class(ref v) => ("CLAS", v.id.to_lowercase()),
+        TES3Object::Faction(ref v) => ("FACT", v.id.to_lowercase()),
+        TES3Object::Race(ref v) => ("RACE", v.id.to_lowercase()),
+        TES3Object::Sound(ref v) => ("SOUN", v.id.to_lowercase()),
+        TES3Object::SoundGen(ref v) => ("SNDG", v.id.to_lowercase()),
+        TES3Object::Skill(ref v) => ("SKIL", format!("{:?}", v.skill_id)),
+        TES3Object::MagicEffect(ref v) => ("MGEF", format!("{:?}", v.effect_id)),
+        TES3Object::Script(ref v) => ("SCPT", v.id.to_lowercase()),
+        TES3Object::Region(ref v) => ("REGN", v.id.to_lowercase()),
+        TES3Object::Birthsign(ref v) => ("BSGN", v.id.to_lowercase()),
+        TES3Object::StartScript(ref v) => ("SSCR", v.id.to_lowercase()),
+        TES3Object::LandscapeTexture(ref v) => ("LTEX", v.id.to_lowercase()),
+        TES3Object::Spell(ref v) => ("SPEL", v.id.to_lowercase()),
+        TES3Object::Static(ref v) => ("STAT", v.id.to_lowercase()),
+        TES3Object::Door(ref v) => ("DOOR", v.id.to_lowercase()),
+        TES3Object::MiscItem(ref v) => ("MISC", v.id.to_lowercase()),
+        TES3Object::Weapon(ref v) => ("WEAP", v.id.to_lowercase()),
+        TES3Object::Container(ref v) => ("CONT", v.id.to_lowercase()),
+        TES3Object::Creature(ref v) => ("CREA", v.id.to_lowercase()),
+        TES3Object::Bodypart(ref v) => ("BODY", v.id.to_lowercase()),
+        TES3Object::Light(ref v) => ("LIGH", v.id.to_lowercase()),
+        TES3Object::Enchanting(ref v) => ("ENCH", v.id.to_lowercase()),
+        TES3Object::Npc(ref v) => ("NPC_", v.id.to_lowercase()),
+        TES3Object::Armor(ref v) => ("ARMO", v.id.to_lowercase()),
+        TES3Object::Clothing(ref v) => ("CLOT", v.id.to_lowercase()),
+        TES3Object::RepairItem(ref v) => ("REPA", v.id.to_lowercase()),
+        TES3Object::Activator(ref v) => ("ACTI", v.id.to_lowercase()),
+        TES3Object::Apparatus(ref v) => ("APPA", v.id.to_lowercase()),
+        TES3Object::Lockpick(ref v) => ("LOCK", v.id.to_lowercase()),
+        TES3Object::Probe(ref v) => ("PROB", v.id.to_lowercase()),
+        TES3Object::Ingredient(ref v) => ("INGR", v.id.to_lowercase()),
+        TES3Object::Book(ref v) => ("BOOK", v.id.to_lowercase()),
+        TES3Object::Alchemy(ref v) => ("ALCH", v.id.to_lowercase()),
+        TES3Object::LeveledItem(ref v) => ("LEVI", v.id.to_lowercase()),
+        TES3Object::LeveledCreature(ref v) => ("LEVC", v.id.to_lowercase()),
+        TES3Object::Cell(ref v) => ("CELL", get_provenance_cell_name(v).to_lowercase()),
+        TES3Object::Landscape(ref v) => ("LAND", format!("{:?}", v.grid)),
+        TES3Object::PathGrid(ref v) => ("PGRD", v.cell.to_lowercase()),
+        TES3Object::Dialogue(ref v) => ("DIAL", v.id.to_lowercase()),
+        TES3Object::DialogueInfo(ref v) => {
+            ("INFO", format!("{active_dial_low}|{}", v.id.to_lowercase()))
+        }
+    }
+}
+
 pub fn show_removed_record_ids(
     removed_record_ids: &[String],
     reason: &str,
@@ -254,6 +316,52 @@ pub fn show_removed_record_ids(
         }
         text.push_str(":\n");
         text.push_str(&removed_record_ids.join("\n"));
+        log.collect_warning(WarningKind::RemovedRecords, &text);
         msg(text, verbosity, cfg, log)
     }
 }
+
+pub fn show_record_notes(
+    notes: &[String],
+    mut text: String,
+    kind: WarningKind,
+    verbosity: u8,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    if notes.is_empty() {
+        return Ok(());
+    }
+    if verbosity < 1 {
+        return Err(anyhow!(
+            "Bug: verbosity passed to show_record_notes should be >= 1, value passed is \"{}\"",
+            verbosity
+        ));
+    }
+    if cfg.verbose < verbosity {
+        msg_no_log(
+            format!(
+                "{text}(check log or add -{} to get list)",
+                "v".repeat(verbosity.into())
+            ),
+            0,
+            cfg,
+        );
+    }
+    text.push_str(":\n");
+    text.push_str(&notes.join("\n"));
+    log.collect_warning(kind, &text);
+    msg(text, verbosity, cfg, log)
+}
+
+// COMMENT: quiet configuration without log file, warnings are collected to be checked by tests
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+pub fn get_test_cfg_and_log() -> (Cfg, Log) {
+    let cfg = Cfg::from_args(["habasi", "--no-log", "--quiet"]).expect("failed to configure");
+    let log = Log {
+        buffer: None,
+        warnings: Some(Vec::new()),
+    };
+    (cfg, log)
+}
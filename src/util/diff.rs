@@ -0,0 +1,321 @@
+use super::msg;
+use crate::{
+    get_provenance_cell_name, get_record_key, nan_to_zero, references_sorted, Cfg, DiffKind, Log,
+    MastId, PluginDiff, RecordDiff, RefDiff, RefrId,
+};
+use anyhow::{Context as _, Result};
+use hashbrown::{HashMap, HashSet};
+use tes3::esp::{Cell, Plugin, Reference, TES3Object};
+
+// COMMENT: occurrence index tells apart several versions of the same record in one plugin
+type RecordKey = (&'static str, String, usize);
+
+pub fn diff_plugins(old_plugin: &Plugin, new_plugin: &Plugin) -> PluginDiff {
+    let old_records = get_keyed_records(old_plugin);
+    let new_records = get_keyed_records(new_plugin);
+    let old_objects: HashMap<&RecordKey, &TES3Object> = old_records
+        .iter()
+        .map(|&(ref key, object)| (key, object))
+        .collect();
+    let new_keys: HashSet<&RecordKey> = new_records.iter().map(|&(ref key, _)| key).collect();
+    let mut diff = PluginDiff::default();
+    for &(ref key, new) in &new_records {
+        if let Some(&old) = old_objects.get(key) {
+            diff_records(key, old, new, &mut diff);
+        } else {
+            push_record_diff(key, DiffKind::Added, &mut diff);
+            if let TES3Object::Cell(ref new_cell) = *new {
+                diff_refs(None, Some(new_cell), &mut diff);
+            }
+        }
+    }
+    for &(ref key, old) in &old_records {
+        if !new_keys.contains(key) {
+            push_record_diff(key, DiffKind::Removed, &mut diff);
+            if let TES3Object::Cell(ref old_cell) = *old {
+                diff_refs(Some(old_cell), None, &mut diff);
+            }
+        }
+    }
+    mark_moved_refs(&mut diff);
+    diff
+}
+
+pub fn show_plugins_diff(old_path: &str, new_path: &str, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let mut old_plugin = Plugin::new();
+    old_plugin
+        .load_path(old_path)
+        .with_context(|| format!("Failed to read plugin {old_path:?}"))?;
+    let mut new_plugin = Plugin::new();
+    new_plugin
+        .load_path(new_path)
+        .with_context(|| format!("Failed to read plugin {new_path:?}"))?;
+    let diff = diff_plugins(&old_plugin, &new_plugin);
+    let text = if diff.is_empty() {
+        if old_plugin.objects == new_plugin.objects {
+            format!("Plugin {new_path:?} is equal to {old_path:?}")
+        } else if diff.unexplained == 0 {
+            format!("Plugin {new_path:?} differs from {old_path:?} only in order of records")
+        } else {
+            format!(
+                "Plugin {new_path:?} differs from {old_path:?} in {} record{} with differences not shown by diff, e.g. NaN coordinates",
+                diff.unexplained,
+                if diff.unexplained == 1 { "" } else { "s" }
+            )
+        }
+    } else {
+        format!(
+            "Plugin {new_path:?} differences from {old_path:?}, {}:\n{}",
+            diff.summary(),
+            diff.show()?.trim_end()
+        )
+    };
+    msg(text, 0, cfg, log)
+}
+
+pub fn is_cell_properties_equal(old_cell: &Cell, new_cell: &Cell) -> bool {
+    new_cell.flags == old_cell.flags
+        && new_cell.name == old_cell.name
+        && new_cell.data == old_cell.data
+        && new_cell.region == old_cell.region
+        && new_cell.map_color == old_cell.map_color
+        && new_cell.water_height == old_cell.water_height
+        && new_cell.atmosphere_data == old_cell.atmosphere_data
+}
+
+fn get_keyed_records(plugin: &Plugin) -> Vec<(RecordKey, &TES3Object)> {
+    let mut active_dial_low = String::new();
+    let mut occurrences: HashMap<(&'static str, String), usize> = HashMap::new();
+    plugin
+        .objects
+        .iter()
+        .map(|object| {
+            if let TES3Object::Dialogue(ref dial) = *object {
+                active_dial_low = dial.id.to_lowercase();
+            }
+            let (record_type, id) = get_record_key(object, &active_dial_low);
+            let occurrence = occurrences.entry((record_type, id.clone())).or_insert(0);
+            let index = *occurrence;
+            *occurrence = occurrence.saturating_add(1);
+            ((record_type, id, index), object)
+        })
+        .collect()
+}
+
+fn push_record_diff(key: &RecordKey, kind: DiffKind, diff: &mut PluginDiff) {
+    diff.records.push(RecordDiff {
+        record_type: key.0,
+        id: key.1.clone(),
+        kind,
+        note: if key.2 == 0 {
+            String::new()
+        } else {
+            format!(" (version {})", key.2.saturating_add(1))
+        },
+    });
+}
+
+fn diff_records(key: &RecordKey, old: &TES3Object, new: &TES3Object, diff: &mut PluginDiff) {
+    #[allow(clippy::wildcard_enum_match_arm)]
+    match (old, new) {
+        (&TES3Object::Cell(ref old_cell), &TES3Object::Cell(ref new_cell)) => {
+            let diffs_len = diff.records.len().saturating_add(diff.refs.len());
+            if !is_cell_properties_equal(old_cell, new_cell) {
+                push_record_diff(key, DiffKind::Changed, diff);
+            }
+            diff_refs(Some(old_cell), Some(new_cell), diff);
+            if diff.records.len().saturating_add(diff.refs.len()) == diffs_len
+                && old_cell != new_cell
+            {
+                diff.unexplained = diff.unexplained.saturating_add(1);
+            }
+        }
+        _ => {
+            if old != new {
+                push_record_diff(key, DiffKind::Changed, diff);
+            }
+        }
+    }
+}
+
+fn diff_refs(old: Option<&Cell>, new: Option<&Cell>, diff: &mut PluginDiff) {
+    let Some(cell) = new.or(old) else {
+        return;
+    };
+    let cell_name = get_provenance_cell_name(cell);
+    let mut push_ref_diff = |reference: &Reference, kind: DiffKind| {
+        diff.refs.push(RefDiff {
+            cell: cell_name.clone(),
+            mast_index: reference.mast_index,
+            refr_index: reference.refr_index,
+            id: reference.id.clone(),
+            kind,
+            note: String::new(),
+        });
+    };
+    if let Some(new_cell) = new {
+        let mut new_refs: Vec<&Reference> = new_cell.references.values().collect();
+        references_sorted(&mut new_refs);
+        for new_ref in new_refs {
+            let old_ref = old.and_then(|old_cell| {
+                old_cell
+                    .references
+                    .get(&(new_ref.mast_index, new_ref.refr_index))
+            });
+            let kind = old_ref.map_or(Some(DiffKind::Added), |previous_ref| {
+                get_ref_change(previous_ref, new_ref)
+            });
+            if let Some(kind) = kind {
+                push_ref_diff(new_ref, kind);
+            }
+        }
+    }
+    if let Some(old_cell) = old {
+        let mut old_refs: Vec<&Reference> = old_cell.references.values().collect();
+        references_sorted(&mut old_refs);
+        for old_ref in old_refs {
+            if !new.is_some_and(|new_cell| {
+                new_cell
+                    .references
+                    .contains_key(&(old_ref.mast_index, old_ref.refr_index))
+            }) {
+                push_ref_diff(old_ref, DiffKind::Removed);
+            }
+        }
+    }
+}
+
+// COMMENT: NaN coordinates are treated as zeroes, references with only position or rotation changed are reported as moved
+fn get_ref_change(old_ref: &Reference, new_ref: &Reference) -> Option<DiffKind> {
+    if old_ref == new_ref {
+        return None;
+    }
+    let old_zeroed = nan_to_zero(old_ref);
+    let new_zeroed = nan_to_zero(new_ref);
+    if old_zeroed == new_zeroed {
+        return None;
+    }
+    let new_not_moved = Reference {
+        translation: old_zeroed.translation,
+        rotation: old_zeroed.rotation,
+        ..new_zeroed
+    };
+    Some(if new_not_moved == old_zeroed {
+        DiffKind::Moved
+    } else {
+        DiffKind::Changed
+    })
+}
+
+// COMMENT: reference removed from one cell and added to another is the same reference moved between cells
+fn mark_moved_refs(diff: &mut PluginDiff) {
+    let removed: HashMap<(MastId, RefrId), (String, String)> = diff
+        .refs
+        .iter()
+        .filter(|reference| reference.kind == DiffKind::Removed)
+        .map(|reference| {
+            (
+                (reference.mast_index, reference.refr_index),
+                (reference.cell.clone(), reference.id.to_lowercase()),
+            )
+        })
+        .collect();
+    let mut moved: HashSet<(MastId, RefrId)> = HashSet::new();
+    for reference in &mut diff.refs {
+        let key = (reference.mast_index, reference.refr_index);
+        if reference.kind == DiffKind::Added {
+            // COMMENT: reused reference index of another object is not a move
+            if let Some(&(ref old_cell, ref old_id_low)) = removed.get(&key) {
+                if *old_id_low == reference.id.to_lowercase() {
+                    reference.kind = DiffKind::Moved;
+                    reference.note = format!(" from cell {old_cell:?}");
+                    moved.insert(key);
+                }
+            }
+        }
+    }
+    diff.refs.retain(|reference| {
+        reference.kind != DiffKind::Removed
+            || !moved.contains(&(reference.mast_index, reference.refr_index))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::diff_plugins;
+    use crate::DiffKind;
+    use tes3::esp::{Cell, Plugin, Reference, Static, TES3Object};
+
+    fn get_static(id: &str, mesh: &str) -> TES3Object {
+        TES3Object::Static(Static {
+            id: id.to_owned(),
+            mesh: mesh.to_owned(),
+            ..Default::default()
+        })
+    }
+
+    fn get_plugin(objects: Vec<TES3Object>) -> Plugin {
+        let mut plugin = Plugin::new();
+        plugin.objects = objects;
+        plugin
+    }
+
+    #[test]
+    fn versions_are_matched_by_occurrence() {
+        let old_plugin = get_plugin(vec![get_static("a", "a.nif"), get_static("a", "b.nif")]);
+        let new_plugin = get_plugin(vec![
+            get_static("a", "a.nif"),
+            get_static("a", "c.nif"),
+            get_static("a", "c.nif"),
+        ]);
+        let diff = diff_plugins(&old_plugin, &new_plugin);
+        let records: Vec<(DiffKind, &str)> = diff
+            .records
+            .iter()
+            .map(|record| (record.kind, record.note.as_str()))
+            .collect();
+        assert!(
+            records
+                == [
+                    (DiffKind::Changed, " (version 2)"),
+                    (DiffKind::Added, " (version 3)")
+                ],
+            "only the second version should be changed and the third one added"
+        );
+    }
+
+    #[test]
+    fn reordered_records_are_not_unexplained() {
+        let old_plugin = get_plugin(vec![get_static("a", "a.nif"), get_static("b", "b.nif")]);
+        let new_plugin = get_plugin(vec![get_static("b", "b.nif"), get_static("a", "a.nif")]);
+        let diff = diff_plugins(&old_plugin, &new_plugin);
+        assert!(diff.is_empty(), "reordered records were reported");
+        assert_eq!(diff.unexplained, 0, "reordered records are unexplained");
+    }
+
+    #[test]
+    fn not_shown_differences_are_counted() {
+        let cell = Cell {
+            references: [(
+                (0, 1),
+                Reference {
+                    refr_index: 1,
+                    id: "a".to_owned(),
+                    translation: [f32::NAN, 0.0, 0.0],
+                    ..Default::default()
+                },
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let old_plugin = get_plugin(vec![TES3Object::Cell(cell.clone())]);
+        let new_plugin = get_plugin(vec![TES3Object::Cell(cell)]);
+        let diff = diff_plugins(&old_plugin, &new_plugin);
+        assert!(diff.is_empty(), "NaN coordinates were reported");
+        assert_eq!(
+            diff.unexplained, 1,
+            "cell with NaN coordinates should be unexplained"
+        );
+    }
+}
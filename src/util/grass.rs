@@ -0,0 +1,201 @@
+use super::prepare_complex_arg_string;
+use crate::{CellExtGrid, Cfg, GrassGridRect, Helper, ListOptions, Out, CRC64};
+use anyhow::{anyhow, Context as _, Result};
+use regex::{Regex, RegexBuilder};
+use std::fmt::Write as _;
+use tes3::esp::{Cell, Reference};
+
+const GRASS_VALUES_SEPARATOR: char = '|';
+const GRASS_GRID_SEPARATOR: char = ':';
+
+pub fn get_grass_density(raw: &str, cfg: &Cfg) -> Result<u8> {
+    parse_grass_density(&prepare_complex_arg_string(
+        raw,
+        &cfg.guts.list_options_prefix_grass_density,
+        "grass_density",
+    )?)
+}
+
+pub fn get_grass_exclude_regions(raw: &str, cfg: &Cfg) -> Result<Vec<String>> {
+    Ok(parse_grass_exclude_regions(&prepare_complex_arg_string(
+        raw,
+        &cfg.guts.list_options_prefix_grass_exclude_regions,
+        "grass_exclude_regions",
+    )?))
+}
+
+pub fn get_grass_exclude_grids(raw: &str, cfg: &Cfg) -> Result<Vec<GrassGridRect>> {
+    parse_grass_exclude_grids(&prepare_complex_arg_string(
+        raw,
+        &cfg.guts.list_options_prefix_grass_exclude_grids,
+        "grass_exclude_grids",
+    )?)
+}
+
+pub fn get_grass_exclude_meshes(raw: &str, cfg: &Cfg) -> Result<Vec<Regex>> {
+    parse_grass_exclude_meshes(&prepare_complex_arg_string(
+        raw,
+        &cfg.guts.list_options_prefix_grass_exclude_meshes,
+        "grass_exclude_meshes",
+    )?)
+}
+
+pub fn parse_grass_density(value: &str) -> Result<u8> {
+    value.parse::<u8>().map_or_else(
+        |_| {
+            Err(anyhow!(
+                "Error: grass_density should be an integer from 0 to 100, value passed is \"{value}\""
+            ))
+        },
+        check_grass_density,
+    )
+}
+
+pub fn check_grass_density(density: u8) -> Result<u8> {
+    if density > 100 {
+        Err(anyhow!(
+            "Error: grass_density should be an integer from 0 to 100, value passed is \"{density}\""
+        ))
+    } else {
+        Ok(density)
+    }
+}
+
+pub fn parse_grass_exclude_regions(value: &str) -> Vec<String> {
+    split_grass_values(value).map(str::to_lowercase).collect()
+}
+
+pub fn parse_grass_exclude_grids(value: &str) -> Result<Vec<GrassGridRect>> {
+    let mut rects = Vec::new();
+    for rect in split_grass_values(value) {
+        let coordinates = rect
+            .split(GRASS_GRID_SEPARATOR)
+            .map(|coordinate| coordinate.trim().parse::<i32>())
+            .collect::<Result<Vec<_>, _>>();
+        let Ok(&[x1, y1, x2, y2]) = coordinates.as_deref() else {
+            return Err(anyhow!(
+                "Error: grass_exclude_grids should consist of rectangles in format \"X1:Y1:X2:Y2\", value passed is \"{rect}\""
+            ));
+        };
+        rects.push(((x1.min(x2), y1.min(y2)), (x1.max(x2), y1.max(y2))));
+    }
+    Ok(rects)
+}
+
+pub fn parse_grass_exclude_meshes(value: &str) -> Result<Vec<Regex>> {
+    let mut patterns = Vec::new();
+    for pattern in split_grass_values(value) {
+        patterns.push(
+            RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| {
+                    format!("Error: grass_exclude_meshes pattern \"{pattern}\" is invalid")
+                })?,
+        );
+    }
+    Ok(patterns)
+}
+
+fn split_grass_values(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(GRASS_VALUES_SEPARATOR)
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+}
+
+pub fn show_grass_list_options(text: &mut String, list_options: &ListOptions) -> Result<()> {
+    if list_options.grass_density < 100 {
+        write!(text, ", grass_density = {}", list_options.grass_density)?;
+    }
+    if !list_options.grass_exclude_regions.is_empty() {
+        write!(
+            text,
+            ", grass_exclude_regions = \"{}\"",
+            list_options
+                .grass_exclude_regions
+                .join(&GRASS_VALUES_SEPARATOR.to_string())
+        )?;
+    }
+    if !list_options.grass_exclude_grids.is_empty() {
+        write!(
+            text,
+            ", grass_exclude_grids = \"{}\"",
+            list_options
+                .grass_exclude_grids
+                .iter()
+                .map(|&((x1, y1), (x2, y2))| format!("{x1}:{y1}:{x2}:{y2}"))
+                .collect::<Vec<_>>()
+                .join(&GRASS_VALUES_SEPARATOR.to_string())
+        )?;
+    }
+    if !list_options.grass_exclude_meshes.is_empty() {
+        write!(
+            text,
+            ", grass_exclude_meshes = \"{}\"",
+            list_options
+                .grass_exclude_meshes
+                .iter()
+                .map(Regex::as_str)
+                .collect::<Vec<_>>()
+                .join(&GRASS_VALUES_SEPARATOR.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+pub fn is_grass_cell_excluded(cell: &Cell, list_options: &ListOptions) -> bool {
+    if cell.is_interior() {
+        return false;
+    }
+    let (x, y) = cell.data.grid;
+    list_options
+        .grass_exclude_grids
+        .iter()
+        .any(|&((x1, y1), (x2, y2))| (x1..=x2).contains(&x) && (y1..=y2).contains(&y))
+        || cell.region.as_ref().is_some_and(|region| {
+            list_options
+                .grass_exclude_regions
+                .contains(&region.to_lowercase())
+        })
+}
+
+pub fn is_grass_kept(
+    reference: &Reference,
+    grid: CellExtGrid,
+    out: &Out,
+    h: &Helper,
+    cfg: &Cfg,
+) -> bool {
+    let id_low = reference.id.to_lowercase();
+    if cfg.advanced.grass_filter.contains(&id_low) {
+        return false;
+    }
+    let list_options = &h.g.list_options;
+    if !list_options.grass_exclude_meshes.is_empty() {
+        if let Some(&(ref stat, _)) = h.g.r.stat.get(&id_low).and_then(|&id| out.stat.get(id)) {
+            if list_options
+                .grass_exclude_meshes
+                .iter()
+                .any(|pattern| pattern.is_match(&stat.mesh))
+            {
+                return false;
+            }
+        }
+    }
+    if list_options.grass_density < 100 {
+        // COMMENT: instances are thinned with hash of cell grid and instance index to get the same result on each run
+        let mut seed = Vec::with_capacity(12);
+        seed.extend_from_slice(&grid.0.to_le_bytes());
+        seed.extend_from_slice(&grid.1.to_le_bytes());
+        seed.extend_from_slice(&reference.refr_index.to_le_bytes());
+        if CRC64
+            .checksum(&seed)
+            .checked_rem(100)
+            .is_some_and(|rest| rest >= u64::from(list_options.grass_density))
+        {
+            return false;
+        }
+    }
+    true
+}
@@ -1,5 +1,5 @@
 use super::{msg, msg_no_log, Log};
-use crate::{Cfg, Helper};
+use crate::{Cfg, Helper, WarningKind};
 use anyhow::{Context as _, Result};
 use std::fmt::Write as _;
 
@@ -44,6 +44,7 @@ pub fn truncate_header_text(
             value.len() - len,
             &value.get(len..).with_context(|| "Bug: indexing slicing")?
         )?;
+        log.collect_warning(WarningKind::TruncatedHeader, &text);
         msg(&text, u8::MAX, cfg, log)?;
         Ok(truncated_value
             .get(..len)
@@ -0,0 +1,554 @@
+use super::{get_cell_name, msg, show_removed_record_ids, Log};
+use crate::{increment, is_cell_properties_equal, Cfg, Helper, MastId, Out, StatsUpdateKind};
+use anyhow::{Context as _, Result};
+use hashbrown::{hash_map::Entry, HashMap};
+use std::{
+    mem::take,
+    path::{Path, PathBuf},
+};
+use tes3::esp::{Cell, Plugin, Reference, TES3Object};
+
+pub fn load_masters(out: &Out, h: &mut Helper, cfg: &Cfg, log: &mut Log) -> Result<()> {
+    for (&(ref master_name, _), global_id) in out.masters.iter().zip(1_u32..) {
+        let master_name_low = master_name.to_lowercase();
+        if h.g.master_records.loaded.contains(&master_name_low) {
+            continue;
+        }
+        h.g.master_records.loaded.push(master_name_low);
+        let text = if let Some(master_path) = get_master_path(master_name, h) {
+            let master = Plugin::from_path(&master_path)
+                .with_context(|| format!("Failed to read master \"{}\"", master_path.display()))?;
+            load_master(master, global_id, out, h)
+                .with_context(|| format!("Failed to load master \"{}\"", master_path.display()))?;
+            format!(
+                "  Master \"{}\" was loaded to find records identical to it",
+                master_path.display()
+            )
+        } else {
+            format!("  Failed to find master \"{master_name}\", records identical to it will not be removed")
+        };
+        msg(text, 1, cfg, log)?;
+    }
+    Ok(())
+}
+
+fn get_master_path(master_name: &str, h: &Helper) -> Option<PathBuf> {
+    let master_name_low = master_name.to_lowercase();
+    if let Some(game_config) = h.t.game_configs.get(h.g.config_index) {
+        if let Some(path) = game_config.load_order.contents.iter().find(|path| {
+            Path::new(path).file_name().is_some_and(|file_name| {
+                file_name.to_string_lossy().to_lowercase() == master_name_low
+            })
+        }) {
+            return Some(PathBuf::from(path));
+        }
+    }
+    [
+        h.l.plugin_info.path.parent(),
+        Some(&*h.g.list_options.indirect.base_dir),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|dir| dir.join(master_name))
+    .find(|path| path.exists())
+}
+
+#[allow(clippy::wildcard_enum_match_arm)]
+fn load_master(master: Plugin, global_id: MastId, out: &Out, h: &mut Helper) -> Result<()> {
+    let mr = &mut h.g.master_records;
+    let mut local_masters: Vec<Option<MastId>> = Vec::new();
+    macro_rules! insert {
+        ($type:ident, $value:ident) => {{
+            mr.$type.insert($value.id.to_lowercase(), $value);
+        }};
+    }
+    for record in master.objects {
+        match record {
+            TES3Object::Header(header) => {
+                local_masters = header
+                    .masters
+                    .iter()
+                    .map(|&(ref name, _)| {
+                        let name_low = name.to_lowercase();
+                        out.masters
+                            .iter()
+                            .zip(1_u32..)
+                            .find(|&(&(ref out_name, _), _)| out_name.to_lowercase() == name_low)
+                            .map(|(_, id)| id)
+                    })
+                    .collect();
+            }
+            TES3Object::GameSetting(v) => insert!(gmst, v),
+            TES3Object::GlobalVariable(v) => insert!(glob, v),
+            TES3Object::Class(v) => insert!(clas, v),
+            TES3Object::Faction(v) => insert!(fact, v),
+            TES3Object::Race(v) => insert!(race, v),
+            TES3Object::Sound(v) => insert!(soun, v),
+            TES3Object::SoundGen(v) => insert!(sndg, v),
+            TES3Object::Skill(v) => {
+                mr.skil.insert(v.skill_id, v);
+            }
+            TES3Object::MagicEffect(v) => {
+                mr.mgef.insert(v.effect_id, v);
+            }
+            TES3Object::Script(v) => insert!(scpt, v),
+            TES3Object::Region(v) => insert!(regn, v),
+            TES3Object::Birthsign(v) => insert!(bsgn, v),
+            TES3Object::StartScript(v) => insert!(sscr, v),
+            TES3Object::Spell(v) => insert!(spel, v),
+            TES3Object::Static(v) => insert!(stat, v),
+            TES3Object::Door(v) => insert!(door, v),
+            TES3Object::MiscItem(v) => insert!(misc, v),
+            TES3Object::Weapon(v) => insert!(weap, v),
+            TES3Object::Container(v) => insert!(cont, v),
+            TES3Object::Creature(v) => insert!(crea, v),
+            TES3Object::Bodypart(v) => insert!(body, v),
+            TES3Object::Light(v) => insert!(ligh, v),
+            TES3Object::Enchanting(v) => insert!(ench, v),
+            TES3Object::Npc(v) => insert!(npc_, v),
+            TES3Object::Armor(v) => insert!(armo, v),
+            TES3Object::Clothing(v) => insert!(clot, v),
+            TES3Object::RepairItem(v) => insert!(repa, v),
+            TES3Object::Activator(v) => insert!(acti, v),
+            TES3Object::Apparatus(v) => insert!(appa, v),
+            TES3Object::Lockpick(v) => insert!(lock, v),
+            TES3Object::Probe(v) => insert!(prob, v),
+            TES3Object::Ingredient(v) => insert!(ingr, v),
+            TES3Object::Book(v) => insert!(book, v),
+            TES3Object::Alchemy(v) => insert!(alch, v),
+            TES3Object::LeveledItem(v) => insert!(levi, v),
+            TES3Object::LeveledCreature(v) => insert!(levc, v),
+            TES3Object::Cell(cell) => {
+                let mut references = HashMap::new();
+                for reference in cell.references.values() {
+                    let mast_index = if reference.mast_index == 0 {
+                        global_id
+                    } else {
+                        // COMMENT: references to masters that are not masters of the output plugin can't be compared
+                        let local_id = usize::try_from(reference.mast_index)
+                            .with_context(|| {
+                                format!(
+                                    "Bug: failed to cast {:?}(mast_index, u32) to usize",
+                                    reference.mast_index
+                                )
+                            })?
+                            .saturating_sub(1);
+                        match local_masters.get(local_id) {
+                            Some(&Some(mast_index)) => mast_index,
+                            _ => continue,
+                        }
+                    };
+                    references.insert(
+                        (mast_index, reference.refr_index),
+                        normalize_reference(reference, mast_index),
+                    );
+                }
+                // COMMENT: cells of later masters are combined with cells of previous masters
+                macro_rules! insert_cell {
+                    ($entry:expr) => {
+                        match $entry {
+                            Entry::Vacant(v) => {
+                                v.insert(Cell { references, ..cell });
+                            }
+                            Entry::Occupied(mut o) => {
+                                let mut master_references = take(&mut o.get_mut().references);
+                                master_references.extend(references);
+                                o.insert(Cell {
+                                    references: master_references,
+                                    ..cell
+                                });
+                            }
+                        }
+                    };
+                }
+                if cell.is_interior() {
+                    insert_cell!(mr.int_cells.entry(cell.name.to_lowercase()));
+                } else {
+                    insert_cell!(mr.ext_cells.entry(cell.data.grid));
+                }
+            }
+            TES3Object::PathGrid(v) => {
+                mr.pgrd.insert((v.cell.to_lowercase(), v.data.grid), v);
+            }
+            _ => continue,
+        }
+    }
+    Ok(())
+}
+
+fn normalize_reference(reference: &Reference, mast_index: MastId) -> Reference {
+    Reference {
+        mast_index,
+        object_count: if reference.object_count == Some(1) {
+            None
+        } else {
+            reference.object_count
+        },
+        scale: if reference.scale == Some(1.0) || reference.deleted.is_some() {
+            None
+        } else {
+            reference.scale
+        },
+        temporary: if reference.destination.is_some() {
+            false
+        } else {
+            reference.temporary
+        },
+        moved_cell: if reference.deleted.is_some() {
+            None
+        } else {
+            reference.moved_cell
+        },
+        ..reference.clone()
+    }
+}
+
+#[allow(clippy::wildcard_enum_match_arm)]
+pub fn count_itm(record: &TES3Object, h: &mut Helper) -> Result<()> {
+    let mr = &h.g.master_records;
+    macro_rules! count {
+        ($type:ident, $key:expr, $value:ident) => {
+            if mr.$type.get(&$key) == Some($value) {
+                h.l.stats.$type(StatsUpdateKind::Itm);
+            }
+        };
+        ($type:ident, $value:ident) => {
+            count!($type, $value.id.to_lowercase(), $value)
+        };
+    }
+    match *record {
+        TES3Object::GameSetting(ref v) => count!(gmst, v),
+        TES3Object::GlobalVariable(ref v) => count!(glob, v),
+        TES3Object::Class(ref v) => count!(clas, v),
+        TES3Object::Faction(ref v) => count!(fact, v),
+        TES3Object::Race(ref v) => count!(race, v),
+        TES3Object::Sound(ref v) => count!(soun, v),
+        TES3Object::SoundGen(ref v) => count!(sndg, v),
+        TES3Object::Skill(ref v) => count!(skil, v.skill_id, v),
+        TES3Object::MagicEffect(ref v) => count!(mgef, v.effect_id, v),
+        TES3Object::Script(ref v) => count!(scpt, v),
+        TES3Object::Region(ref v) => count!(regn, v),
+        TES3Object::Birthsign(ref v) => count!(bsgn, v),
+        TES3Object::StartScript(ref v) => count!(sscr, v),
+        TES3Object::Spell(ref v) => count!(spel, v),
+        TES3Object::Static(ref v) => count!(stat, v),
+        TES3Object::Door(ref v) => count!(door, v),
+        TES3Object::MiscItem(ref v) => count!(misc, v),
+        TES3Object::Weapon(ref v) => count!(weap, v),
+        TES3Object::Container(ref v) => count!(cont, v),
+        TES3Object::Creature(ref v) => count!(crea, v),
+        TES3Object::Bodypart(ref v) => count!(body, v),
+        TES3Object::Light(ref v) => count!(ligh, v),
+        TES3Object::Enchanting(ref v) => count!(ench, v),
+        TES3Object::Npc(ref v) => count!(npc_, v),
+        TES3Object::Armor(ref v) => count!(armo, v),
+        TES3Object::Clothing(ref v) => count!(clot, v),
+        TES3Object::RepairItem(ref v) => count!(repa, v),
+        TES3Object::Activator(ref v) => count!(acti, v),
+        TES3Object::Apparatus(ref v) => count!(appa, v),
+        TES3Object::Lockpick(ref v) => count!(lock, v),
+        TES3Object::Probe(ref v) => count!(prob, v),
+        TES3Object::Ingredient(ref v) => count!(ingr, v),
+        TES3Object::Book(ref v) => count!(book, v),
+        TES3Object::Alchemy(ref v) => count!(alch, v),
+        TES3Object::LeveledItem(ref v) => count!(levi, v),
+        TES3Object::LeveledCreature(ref v) => count!(levc, v),
+        TES3Object::PathGrid(ref v) => count!(pgrd, (v.cell.to_lowercase(), v.data.grid), v),
+        TES3Object::Cell(ref cell) => {
+            let master_cell = if cell.is_interior() {
+                mr.int_cells.get(&cell.name.to_lowercase())
+            } else {
+                mr.ext_cells.get(&cell.data.grid)
+            };
+            let Some(master_cell) = master_cell else {
+                return Ok(());
+            };
+            let mut instances_itm: usize = 0;
+            for reference in cell.references.values() {
+                let Some(local_master) =
+                    h.l.masters
+                        .iter()
+                        .find(|x| x.local_id == reference.mast_index)
+                else {
+                    continue;
+                };
+                let mast_index = local_master.global_id;
+                if master_cell
+                    .references
+                    .get(&(mast_index, reference.refr_index))
+                    == Some(&normalize_reference(reference, mast_index))
+                {
+                    instances_itm = increment!(instances_itm);
+                }
+            }
+            if instances_itm == cell.references.len() && is_cell_properties_equal(master_cell, cell)
+            {
+                h.l.stats.cell(StatsUpdateKind::Itm);
+            }
+            h.l.stats.instances_itm_add_count(instances_itm)?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_lines)]
+pub fn clean_itm(
+    name: &str,
+    out: &mut Out,
+    h: &mut Helper,
+    cfg: &Cfg,
+    log: &mut Log,
+) -> Result<()> {
+    let mr = &h.g.master_records;
+    let stats = &mut h.g.stats;
+    let mut removed_record_ids = Vec::new();
+    // COMMENT: LTEX and LAND are not compared, because texture indices are remapped on merging
+    macro_rules! clean {
+        ($type:ident, $value:ident => $key:expr) => {
+            let mut records = Vec::with_capacity(out.$type.len());
+            for record in take(&mut out.$type) {
+                let $value = &record.0;
+                let key = $key;
+                if mr.$type.get(&key) == Some($value) {
+                    removed_record_ids.push(format!(
+                        "    Record {}: {:?} was excluded from the result because it's identical to master",
+                        stringify!($type).to_uppercase(),
+                        key
+                    ));
+                    stats.$type(StatsUpdateKind::Excluded);
+                } else {
+                    records.push(record);
+                }
+            }
+            out.$type = records;
+        };
+        ($type:ident) => {
+            clean!($type, v => v.id.to_lowercase());
+        };
+    }
+    clean!(gmst);
+    clean!(glob);
+    clean!(clas);
+    clean!(fact);
+    clean!(race);
+    clean!(soun);
+    clean!(sndg);
+    clean!(skil, v => v.skill_id);
+    clean!(mgef, v => v.effect_id);
+    clean!(scpt);
+    clean!(regn);
+    clean!(bsgn);
+    clean!(sscr);
+    clean!(spel);
+    clean!(stat);
+    clean!(door);
+    clean!(misc);
+    clean!(weap);
+    clean!(cont);
+    clean!(crea);
+    clean!(body);
+    clean!(ligh);
+    clean!(ench);
+    clean!(npc_);
+    clean!(armo);
+    clean!(clot);
+    clean!(repa);
+    clean!(acti);
+    clean!(appa);
+    clean!(lock);
+    clean!(prob);
+    clean!(ingr);
+    clean!(book);
+    clean!(alch);
+    clean!(levi);
+    clean!(levc);
+    clean!(pgrd, v => (v.cell.to_lowercase(), v.data.grid));
+    let mut instances_removed: usize = 0;
+    let mut cells = Vec::with_capacity(out.cell.len());
+    for (mut last, prevs) in take(&mut out.cell) {
+        let master_cell = if last.is_interior() {
+            mr.int_cells.get(&last.name.to_lowercase())
+        } else {
+            mr.ext_cells.get(&last.data.grid)
+        };
+        if let Some(master_cell) = master_cell {
+            let references_len = last.references.len();
+            last.references.retain(|key, reference| {
+                key.0 == 0
+                    || master_cell.references.get(key)
+                        != Some(&normalize_reference(reference, key.0))
+            });
+            instances_removed = instances_removed
+                .checked_add(
+                    references_len
+                        .checked_sub(last.references.len())
+                        .with_context(|| {
+                            "Bug: overflow subtracting cell references count after cleaning"
+                        })?,
+                )
+                .with_context(|| "Bug: overflow adding cleaned cell references count")?;
+            if last.references.is_empty() && is_cell_properties_equal(master_cell, &last) {
+                removed_record_ids.push(format!(
+                    "    Record CELL: {} was excluded from the result because it's identical to master",
+                    get_cell_name(&last)
+                ));
+                stats.cell(StatsUpdateKind::Excluded);
+                continue;
+            }
+        }
+        cells.push((last, prevs));
+    }
+    out.cell = cells;
+    if instances_removed > 0 {
+        let text = format!(
+            "  {} instance{} identical to master {} excluded from \"{}\"",
+            instances_removed,
+            if instances_removed == 1 { "" } else { "s" },
+            if instances_removed == 1 {
+                "was"
+            } else {
+                "were"
+            },
+            name
+        );
+        msg(text, 1, cfg, log)?;
+    }
+    show_removed_record_ids(
+        &removed_record_ids,
+        "\"clean_itm\" and record identical to master",
+        name,
+        1,
+        cfg,
+        log,
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{clean_itm, load_master, normalize_reference};
+    use crate::{get_test_cfg_and_log, Helper, Out};
+    use hashbrown::HashMap;
+    use tes3::esp::{Cell, Header, LandscapeTexture, Plugin, Reference, Static, TES3Object};
+
+    fn get_static(id: &str, mesh: &str) -> Static {
+        Static {
+            id: id.to_owned(),
+            mesh: mesh.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn get_reference(mast_index: u32, refr_index: u32, id: &str) -> Reference {
+        Reference {
+            mast_index,
+            refr_index,
+            id: id.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    fn get_cell(references: Vec<((u32, u32), Reference)>) -> Cell {
+        Cell {
+            references: references.into_iter().collect::<HashMap<_, _>>(),
+            ..Default::default()
+        }
+    }
+
+    fn get_master_out(objects: Vec<TES3Object>) -> (Out, Helper) {
+        let mut h = Helper::new();
+        let mut out = Out::default();
+        out.masters.push(("Master.esm".to_owned(), 0));
+        let mut master = Plugin::new();
+        master.objects = objects;
+        master
+            .objects
+            .insert(0, TES3Object::Header(Header::default()));
+        load_master(master, 1, &out, &mut h).expect("failed to load master");
+        (out, h)
+    }
+
+    #[test]
+    fn default_values_are_normalized() {
+        let reference = Reference {
+            object_count: Some(1),
+            scale: Some(1.0),
+            ..get_reference(0, 1, "a")
+        };
+        let normalized = normalize_reference(&reference, 2);
+        assert_eq!(normalized.mast_index, 2, "master index wasn't replaced");
+        assert_eq!(
+            normalized.object_count, None,
+            "default object count wasn't dropped"
+        );
+        assert_eq!(normalized.scale, None, "default scale wasn't dropped");
+    }
+
+    #[test]
+    fn records_and_references_identical_to_master_are_removed() {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let (mut out, mut h) = get_master_out(vec![
+            TES3Object::Static(get_static("a", "a.nif")),
+            TES3Object::Static(get_static("b", "b.nif")),
+            TES3Object::Cell(get_cell(vec![((0, 1), get_reference(0, 1, "a"))])),
+        ]);
+        out.stat = vec![
+            (get_static("a", "a.nif"), Vec::new()),
+            (get_static("b", "changed.nif"), Vec::new()),
+        ];
+        out.cell = vec![(
+            get_cell(vec![
+                ((1, 1), get_reference(1, 1, "a")),
+                ((0, 2), get_reference(0, 2, "b")),
+            ]),
+            Vec::new(),
+        )];
+        clean_itm("Test.esp", &mut out, &mut h, &cfg, &mut log).expect("failed to clean");
+        assert_eq!(
+            out.stat
+                .iter()
+                .map(|&(ref stat, _)| stat.id.as_str())
+                .collect::<Vec<_>>(),
+            ["b"],
+            "only the changed static should be kept"
+        );
+        let &(ref cell, _) = out
+            .cell
+            .first()
+            .expect("cell with new reference was removed");
+        assert_eq!(
+            cell.references.keys().copied().collect::<Vec<_>>(),
+            [(0, 2)],
+            "only the new reference should be kept"
+        );
+    }
+
+    #[test]
+    fn cell_identical_to_master_is_removed() {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let (mut out, mut h) = get_master_out(vec![TES3Object::Cell(get_cell(vec![(
+            (0, 1),
+            get_reference(0, 1, "a"),
+        )]))]);
+        out.cell = vec![(
+            get_cell(vec![((1, 1), get_reference(1, 1, "a"))]),
+            Vec::new(),
+        )];
+        clean_itm("Test.esp", &mut out, &mut h, &cfg, &mut log).expect("failed to clean");
+        assert!(out.cell.is_empty(), "cell identical to master was kept");
+    }
+
+    #[test]
+    fn landscape_textures_are_not_cleaned() {
+        let (cfg, mut log) = get_test_cfg_and_log();
+        let ltex = LandscapeTexture {
+            id: "a".to_owned(),
+            ..Default::default()
+        };
+        let (mut out, mut h) = get_master_out(vec![TES3Object::LandscapeTexture(ltex.clone())]);
+        out.ltex = vec![(ltex, Vec::new())];
+        clean_itm("Test.esp", &mut out, &mut h, &cfg, &mut log).expect("failed to clean");
+        assert_eq!(out.ltex.len(), 1, "remapped landscape texture was removed");
+    }
+}
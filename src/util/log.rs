@@ -1,5 +1,5 @@
 use super::{create_dir_early, msg};
-use crate::Cfg;
+use crate::{Cfg, Warning, WarningKind};
 use anyhow::{anyhow, Context as _, Result};
 use fs_err::{rename, File};
 use std::{
@@ -9,12 +9,16 @@ use std::{
 
 pub struct Log {
     pub(crate) buffer: Option<BufWriter<File>>,
+    pub(crate) warnings: Option<Vec<Warning>>,
 }
 
 impl Log {
     pub(crate) fn new(cfg: &Cfg) -> Result<Self> {
         if cfg.no_log {
-            Ok(Self { buffer: None })
+            Ok(Self {
+                buffer: None,
+                warnings: None,
+            })
         } else {
             let log = match cfg.log {
                 None => return Err(anyhow!("Failed to get log file name")),
@@ -25,7 +29,10 @@ impl Log {
             let buffer = Some(BufWriter::new(File::create(log).with_context(|| {
                 format!("Failed to create/open log file \"{}\"", log.display())
             })?));
-            let mut result = Self { buffer };
+            let mut result = Self {
+                buffer,
+                warnings: None,
+            };
             if !log_backup_message.is_empty() {
                 msg(log_backup_message, 3, cfg, &mut result)?;
             }
@@ -38,6 +45,16 @@ impl Log {
             .as_mut()
             .map_or_else(|| Ok(()), |buffer| writeln!(buffer, "{}", text.as_ref()))
     }
+
+    // COMMENT: warnings are collected only for library users
+    pub(crate) fn collect_warning<S: AsRef<str>>(&mut self, kind: WarningKind, text: S) {
+        if let Some(ref mut warnings) = self.warnings {
+            warnings.push(Warning {
+                kind,
+                text: text.as_ref().to_owned(),
+            });
+        }
+    }
 }
 
 fn backup_log_file(log_file: &PathBuf, backup_suffix: &str) -> String {
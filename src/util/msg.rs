@@ -1,5 +1,5 @@
 use super::Log;
-use crate::Cfg;
+use crate::{Cfg, WarningKind};
 use anyhow::{anyhow, Context as _, Result};
 
 const PREFIX_IGNORED_IMPORTANT_ERROR: &str = "Ignored important error: ";
@@ -38,6 +38,7 @@ pub fn err_or_ignore<S: AsRef<str>>(
     let body = text.as_ref();
     if ignore {
         let message = format!("{PREFIX_IGNORED_IMPORTANT_ERROR}{body}");
+        log.collect_warning(WarningKind::IgnoredError, &message);
         msg(message, 0, cfg, log)
     } else {
         Err(anyhow!(format!(
@@ -0,0 +1,201 @@
+use super::msg;
+use crate::{Cfg, Log, PluginCacheKey, CRC64};
+use anyhow::{Context as _, Result};
+use fs_err::{
+    canonicalize, create_dir_all, metadata, remove_dir_all, remove_file, rename, write, File,
+};
+use std::{
+    io::{BufReader, Read, Result as IOResult},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::UNIX_EPOCH,
+};
+use tes3::esp::{Plugin, TES3Object};
+
+pub fn prepare_plugin_cache(cfg: &Cfg, log: &mut Log) -> Result<()> {
+    let dir = &cfg.plugin_cache.dir;
+    if cfg.plugin_cache.clear {
+        let text = if dir.exists() {
+            remove_dir_all(dir).with_context(|| {
+                format!(
+                    "Failed to remove plugin cache directory \"{}\"",
+                    dir.display()
+                )
+            })?;
+            format!("Plugin cache \"{}\" was cleared", dir.display())
+        } else {
+            format!("Plugin cache \"{}\" doesn't exist", dir.display())
+        };
+        msg(text, 0, cfg, log)?;
+    }
+    if cfg.plugin_cache.enabled {
+        create_dir_all(dir).with_context(|| {
+            format!(
+                "Failed to create plugin cache directory \"{}\"",
+                dir.display()
+            )
+        })?;
+        let text = format!("Plugin cache \"{}\" is enabled", dir.display());
+        msg(text, 1, cfg, log)?;
+    }
+    Ok(())
+}
+
+// COMMENT: any problem with cache falls back to parsing, so that parsing errors are reported the usual way
+pub fn load_plugin(plugin_pathstring: &str, cache_dir: Option<&Path>) -> IOResult<Plugin> {
+    let Some((cache_path, key)) =
+        cache_dir.and_then(|dir| get_plugin_cache_key(plugin_pathstring, dir))
+    else {
+        return Plugin::from_path(plugin_pathstring);
+    };
+    if let Some(plugin) = read_plugin_cache(&cache_path, &key) {
+        return Ok(plugin);
+    }
+    let plugin = Plugin::from_path(plugin_pathstring)?;
+    write_plugin_cache(&cache_path, &key, &plugin);
+    Ok(plugin)
+}
+
+fn get_plugin_cache_key(plugin_pathstring: &str, dir: &Path) -> Option<(PathBuf, PluginCacheKey)> {
+    let path = canonicalize(plugin_pathstring).ok()?;
+    let path_string = path.to_string_lossy().into_owned();
+    let plugin_metadata = metadata(&path).ok()?;
+    let mtime = plugin_metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?;
+    let cache_path = dir.join(format!(
+        "{:016x}.cache",
+        CRC64.checksum(path_string.as_bytes())
+    ));
+    let key = PluginCacheKey {
+        version: env!("CARGO_PKG_VERSION").to_owned(),
+        path: path_string,
+        size: plugin_metadata.len(),
+        mtime_secs: mtime.as_secs(),
+        mtime_nanos: mtime.subsec_nanos(),
+    };
+    Some((cache_path, key))
+}
+
+fn read_plugin_cache(cache_path: &Path, key: &PluginCacheKey) -> Option<Plugin> {
+    decode_plugin_cache(BufReader::new(File::open(cache_path).ok()?), key)
+}
+
+fn decode_plugin_cache<R: Read>(mut reader: R, key: &PluginCacheKey) -> Option<Plugin> {
+    let cached_key: PluginCacheKey = rmp_serde::from_read(&mut reader).ok()?;
+    if cached_key != *key {
+        return None;
+    }
+    let objects: Vec<TES3Object> = rmp_serde::from_read(&mut reader).ok()?;
+    let mut plugin = Plugin::new();
+    plugin.objects = objects;
+    Some(plugin)
+}
+
+// COMMENT: cache is written into unique temporary file first to never leave partially written cache behind, even when the same plugin is listed twice
+fn write_plugin_cache(cache_path: &Path, key: &PluginCacheKey, plugin: &Plugin) {
+    static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let Some(bytes) = encode_plugin_cache(key, plugin) else {
+        return;
+    };
+    let tmp_path = cache_path.with_extension(format!(
+        "{}.{}.tmp",
+        process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    if write(&tmp_path, bytes).is_err() || rename(&tmp_path, cache_path).is_err() {
+        drop(remove_file(&tmp_path));
+    }
+}
+
+fn encode_plugin_cache(key: &PluginCacheKey, plugin: &Plugin) -> Option<Vec<u8>> {
+    let mut bytes = rmp_serde::to_vec_named(key).ok()?;
+    rmp_serde::encode::write_named(&mut bytes, &plugin.objects).ok()?;
+    Some(bytes)
+}
+
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+mod tests {
+    use super::{decode_plugin_cache, encode_plugin_cache};
+    use crate::PluginCacheKey;
+    use hashbrown::HashMap;
+    use tes3::esp::{Cell, Header, Plugin, Reference, Static, TES3Object};
+
+    fn get_key() -> PluginCacheKey {
+        PluginCacheKey {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            path: "Test.esp".to_owned(),
+            size: 1,
+            mtime_secs: 2,
+            mtime_nanos: 3,
+        }
+    }
+
+    fn get_plugin() -> Plugin {
+        let mut references = HashMap::new();
+        references.insert(
+            (0, 1),
+            Reference {
+                mast_index: 0,
+                refr_index: 1,
+                id: "test_static".to_owned(),
+                translation: [1.5, -2.25, 1024.125],
+                rotation: [0.0, 0.5, -3.0],
+                scale: Some(1.25),
+                ..Default::default()
+            },
+        );
+        let mut plugin = Plugin::new();
+        plugin.objects = vec![
+            TES3Object::Header(Header {
+                num_objects: 2,
+                masters: vec![("Morrowind.esm".to_owned(), 79_837_557)],
+                ..Default::default()
+            }),
+            TES3Object::Static(Static {
+                id: "test_static".to_owned(),
+                mesh: "test\\test.nif".to_owned(),
+                ..Default::default()
+            }),
+            TES3Object::Cell(Cell {
+                name: "Test Cell".to_owned(),
+                references,
+                ..Default::default()
+            }),
+        ];
+        plugin
+    }
+
+    #[test]
+    fn round_trip_is_byte_identical() {
+        let plugin = get_plugin();
+        let bytes = encode_plugin_cache(&get_key(), &plugin).expect("failed to encode cache");
+        let cached_plugin =
+            decode_plugin_cache(bytes.as_slice(), &get_key()).expect("failed to decode cache");
+        assert_eq!(
+            cached_plugin
+                .save_bytes()
+                .expect("failed to save cached plugin"),
+            plugin.save_bytes().expect("failed to save plugin"),
+            "cached plugin differs from the original one"
+        );
+    }
+
+    #[test]
+    fn changed_key_is_rejected() {
+        let plugin = get_plugin();
+        let bytes = encode_plugin_cache(&get_key(), &plugin).expect("failed to encode cache");
+        let changed_key = PluginCacheKey {
+            size: 4,
+            ..get_key()
+        };
+        assert!(
+            decode_plugin_cache(bytes.as_slice(), &changed_key).is_none(),
+            "cache with different key was accepted"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use super::{msg, Log};
-use crate::{Cfg, Helper, IgnoredRefError, Out, PluginName};
+use crate::{Cfg, Helper, IgnoredRefError, Out, PluginName, WarningKind};
 use anyhow::{anyhow, Context as _, Result};
 use std::fmt::Write as _;
 use tes3::esp::Reference;
@@ -154,7 +154,18 @@ pub fn show_ignored_ref_errors(
                 master.master, master.cell_counter, master.ref_counter, master.first_encounter,
             )?;
         }
+        log.collect_warning(WarningKind::IgnoredRefErrors, &text);
         msg(text, 0, cfg, log)?;
     }
     Ok(())
 }
+
+pub fn nan_to_zero(reference: &Reference) -> Reference {
+    let mut res = reference.to_owned();
+    for float in res.translation.iter_mut().chain(res.rotation.iter_mut()) {
+        if float.is_nan() {
+            *float = 0.0;
+        }
+    }
+    res
+}
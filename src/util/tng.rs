@@ -6,7 +6,6 @@ use std::path::{Path, PathBuf};
 pub fn process_turn_normal_grass(
     name: &str,
     out: &mut Out,
-    old_plugin: &mut Plugin,
     h: &mut Helper,
     cfg: &Cfg,
     log: &mut Log,
@@ -18,11 +17,13 @@ pub fn process_turn_normal_grass(
             plugin_grass_name,
             mut plugin_grass,
         ) = make_turn_normal_grass(name, out, h, cfg, log)?;
+        // COMMENT: previous output plugin of the list may be kept loaded in shared buffer to seed reference indices
+        let mut old_plugin = Plugin::new();
         if !h.g.list_options.exclude_deleted_records {
             write_output_plugin(
                 &plugin_deleted_content_name,
                 &mut plugin_deleted_content,
-                old_plugin,
+                &mut old_plugin,
                 2,
                 h,
                 cfg,
@@ -35,7 +36,7 @@ pub fn process_turn_normal_grass(
         write_output_plugin(
             &plugin_grass_name,
             &mut plugin_grass,
-            old_plugin,
+            &mut old_plugin,
             2,
             h,
             cfg,